@@ -1,54 +1,307 @@
-use super::parser::Node;
-use std::io::Result;
+use super::parser::{Node, NodeKind, Type};
+use std::io::{Result, Write};
+
+/// System V AMD64 ABI: integer arguments are passed in these registers in order,
+/// the rest are passed on the stack.
+const ARGUMENT_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+const ARGUMENT_REGISTERS_32: [&str; 6] = ["edi", "esi", "edx", "ecx", "r8d", "r9d"];
+const ARGUMENT_REGISTERS_16: [&str; 6] = ["di", "si", "dx", "cx", "r8w", "r9w"];
+const ARGUMENT_REGISTERS_8: [&str; 6] = ["dil", "sil", "dl", "cl", "r8b", "r9b"];
+
+/// a switch with at least this many cases may jump through a table
+const JUMP_TABLE_MIN_CASES: usize = 4;
+/// the table may have at most this many entries for each case,
+/// the entries between the cases jump to default
+const JUMP_TABLE_MAX_DENSITY: usize = 3;
+
+/// the i-th argument register in the width of the type
+fn argument_register(i: usize, ty: &Type) -> &'static str {
+    match ty.size() {
+        1 => ARGUMENT_REGISTERS_8[i],
+        2 => ARGUMENT_REGISTERS_16[i],
+        4 => ARGUMENT_REGISTERS_32[i],
+        _ => ARGUMENT_REGISTERS[i],
+    }
+}
+
+/// rax in the width of the type
+fn rax(ty: &Type) -> &'static str {
+    match ty.size() {
+        1 => "al",
+        2 => "ax",
+        4 => "eax",
+        _ => "rax",
+    }
+}
+
+/// every expression is typed by the semantic analysis before the code generation
+fn expect_ty(ty: Option<Type>) -> Type {
+    ty.expect("the node is not typed")
+}
+
+/// the smallest value and the index of the case for every value from it
+/// when the cases are dense enough for a jump table
+fn jump_table(cases: &[isize]) -> Option<(isize, Vec<Option<usize>>)> {
+    let min = *cases.iter().min()?;
+    let max = *cases.iter().max()?;
+    let size = (max as i128 - min as i128 + 1) as u128;
+    if cases.len() < JUMP_TABLE_MIN_CASES || size > (cases.len() * JUMP_TABLE_MAX_DENSITY) as u128 {
+        return None;
+    }
+    let mut table = vec![None; size as usize];
+    for (i, value) in cases.iter().enumerate() {
+        table[(*value as i128 - min as i128) as usize] = Some(i);
+    }
+    Some((min, table))
+}
+
+/// the directive which places a value of the type in a section
+fn data_directive(ty: &Type) -> &'static str {
+    match ty.size() {
+        1 => ".byte",
+        2 => ".short",
+        4 => ".long",
+        _ => ".quad",
+    }
+}
+
+/// rax and rdi for arithmetic in the type
+/// which is int or long after the usual arithmetic conversion
+fn registers(ty: &Type) -> (&'static str, &'static str) {
+    match ty.size() {
+        8 => ("rax", "rdi"),
+        _ => ("eax", "edi"),
+    }
+}
+
+pub struct Codegen<W: Write> {
+    /// where the assembly is written
+    out: W,
 
-pub struct Codegen {
     block_index: usize,
+
+    /// labels which break and continue jump to, the innermost is the last
+    breaks: Vec<String>,
+    continues: Vec<String>,
+
+    /// name of the function being generated
+    function: String,
+
+    /// the label index and the case values of the switch statements
+    /// enclosing the current position, the innermost is the last
+    switches: Vec<(usize, Vec<isize>)>,
 }
 
-impl Codegen {
-    pub fn new() -> Self {
-        Self { block_index: 0 }
+impl<W: Write> Codegen<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            block_index: 0,
+            breaks: vec![],
+            continues: vec![],
+            switches: vec![],
+            function: String::new(),
+        }
     }
     fn if_n(&mut self, condition: Node, then: Node, else_body: Option<Node>) -> Result<()> {
         let block_index = self.block_index;
         self.block_index += 1;
 
-        self.node(condition)?;
-        println!("  pop rax");
-        println!("  cmp rax, 0");
+        self.expr(condition)?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp rax, 0")?;
 
         if let Some(else_body) = else_body {
-            println!("  je .Lelse{}", block_index);
-            self.node(then)?;
-            println!("  jmp .Lend{}", block_index);
-            println!(".Lelse{}:", block_index);
-            self.node(else_body)?;
+            writeln!(self.out, "  je .Lelse{}", block_index)?;
+            self.stmt(then)?;
+            writeln!(self.out, "  jmp .Lend{}", block_index)?;
+            writeln!(self.out, ".Lelse{}:", block_index)?;
+            self.stmt(else_body)?;
         } else {
-            println!("  je .Lend{}", block_index);
-            self.node(then)?;
+            writeln!(self.out, "  je .Lend{}", block_index)?;
+            self.stmt(then)?;
         }
 
-        println!(".Lend{}:", block_index);
+        writeln!(self.out, ".Lend{}:", block_index)?;
         Ok(())
     }
 
+    /// the value of the evaluated branch is left on the stack
+    fn conditional(&mut self, condition: Node, then: Node, else_n: Node) -> Result<()> {
+        let block_index = self.block_index;
+        self.block_index += 1;
+
+        self.expr(condition)?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp rax, 0")?;
+        writeln!(self.out, "  je .Lelse{}", block_index)?;
+        self.expr(then)?;
+        writeln!(self.out, "  jmp .Lend{}", block_index)?;
+        writeln!(self.out, ".Lelse{}:", block_index)?;
+        self.expr(else_n)?;
+        writeln!(self.out, ".Lend{}:", block_index)?;
+        Ok(())
+    }
+
+    fn comma(&mut self, left: Node, right: Node) -> Result<()> {
+        self.expr(left)?;
+        writeln!(self.out, "  pop rax")?;
+        self.expr(right)
+    }
+
     fn whlie_n(&mut self, condition: Node, body: Node) -> Result<()> {
         let block_index = self.block_index;
         self.block_index += 1;
 
-        println!(".Lbegin{}:", block_index);
-        self.node(condition)?;
+        writeln!(self.out, ".Lbegin{}:", block_index)?;
+        self.expr(condition)?;
 
-        println!("  pop rax");
-        println!("  cmp rax, 0");
-        println!("  je .Lend{}", block_index);
-        self.node(body)?;
-        println!("  jmp .Lbegin{}", block_index);
-        println!(".Lend{}:", block_index);
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp rax, 0")?;
+        writeln!(self.out, "  je .Lend{}", block_index)?;
+        self.loop_body(body, block_index, format!(".Lbegin{}", block_index))?;
+        writeln!(self.out, "  jmp .Lbegin{}", block_index)?;
+        writeln!(self.out, ".Lend{}:", block_index)?;
 
         Ok(())
     }
 
+    /// break jumps to .Lend{block_index} and continue jumps to the label
+    fn loop_body(&mut self, body: Node, block_index: usize, continue_label: String) -> Result<()> {
+        self.breaks.push(format!(".Lend{}", block_index));
+        self.continues.push(continue_label);
+        let result = self.stmt(body);
+        self.breaks.pop();
+        self.continues.pop();
+        result
+    }
+
+    fn do_while(&mut self, body: Node, condition: Node) -> Result<()> {
+        let block_index = self.block_index;
+        self.block_index += 1;
+
+        writeln!(self.out, ".Lbegin{}:", block_index)?;
+        self.loop_body(body, block_index, format!(".Lcontinue{}", block_index))?;
+        writeln!(self.out, ".Lcontinue{}:", block_index)?;
+        self.expr(condition)?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp rax, 0")?;
+        writeln!(self.out, "  jne .Lbegin{}", block_index)?;
+        writeln!(self.out, ".Lend{}:", block_index)?;
+        Ok(())
+    }
+
+    /// the case at the index jumps to .Lcase{block_index}_{index}
+    fn switch(
+        &mut self,
+        condition: Node,
+        body: Node,
+        cases: Vec<isize>,
+        default: bool,
+    ) -> Result<()> {
+        let block_index = self.block_index;
+        self.block_index += 1;
+        let otherwise = if default {
+            format!(".Ldefault{}", block_index)
+        } else {
+            format!(".Lend{}", block_index)
+        };
+
+        self.expr(condition)?;
+        writeln!(self.out, "  pop rax")?;
+        match jump_table(&cases) {
+            Some((min, table)) => {
+                writeln!(self.out, "  mov rdi, {}", min)?;
+                writeln!(self.out, "  sub rax, rdi")?;
+                writeln!(self.out, "  cmp rax, {}", table.len() - 1)?;
+                writeln!(self.out, "  ja {}", otherwise)?;
+                writeln!(self.out, "  lea rdi, [rip + .Ltable{}]", block_index)?;
+                writeln!(self.out, "  movsxd rax, dword ptr [rdi + rax * 4]")?;
+                writeln!(self.out, "  add rax, rdi")?;
+                writeln!(self.out, "  jmp rax")?;
+                // offsets from the table, which do not need relocations
+                writeln!(self.out, "  .section .rodata")?;
+                writeln!(self.out, "  .p2align 2")?;
+                writeln!(self.out, ".Ltable{}:", block_index)?;
+                for entry in table {
+                    match entry {
+                        Some(i) => writeln!(
+                            self.out,
+                            "  .long .Lcase{}_{} - .Ltable{}",
+                            block_index, i, block_index
+                        )?,
+                        None => {
+                            writeln!(self.out, "  .long {} - .Ltable{}", otherwise, block_index)?
+                        }
+                    }
+                }
+                writeln!(self.out, "  .text")?;
+            }
+            None => {
+                for (i, value) in cases.iter().enumerate() {
+                    writeln!(self.out, "  mov rdi, {}", value)?;
+                    writeln!(self.out, "  cmp rax, rdi")?;
+                    writeln!(self.out, "  je .Lcase{}_{}", block_index, i)?;
+                }
+                writeln!(self.out, "  jmp {}", otherwise)?;
+            }
+        }
+
+        self.breaks.push(format!(".Lend{}", block_index));
+        self.switches.push((block_index, cases));
+        let result = self.stmt(body);
+        self.breaks.pop();
+        self.switches.pop();
+        result?;
+        writeln!(self.out, ".Lend{}:", block_index)?;
+        Ok(())
+    }
+
+    fn case(&mut self, value: Node, node: Node) -> Result<()> {
+        let (block_index, cases) = self.switches.last().expect("case in a switch");
+        let i = match value.kind {
+            NodeKind::Number(n) => cases.iter().position(|value| *value == n),
+            _ => None,
+        }
+        .expect("constant case value");
+        writeln!(self.out, ".Lcase{}_{}:", block_index, i)?;
+        self.stmt(node)
+    }
+
+    fn default(&mut self, node: Node) -> Result<()> {
+        let (block_index, _) = self.switches.last().expect("default in a switch");
+        writeln!(self.out, ".Ldefault{}:", block_index)?;
+        self.stmt(node)
+    }
+
+    /// labels in the source are unique in the function,
+    /// and the dots keep them apart from the numbered labels
+    fn source_label(&self, name: &str) -> String {
+        format!(".Llabel.{}.{}", self.function, name)
+    }
+
+    fn label(&mut self, name: String, node: Node) -> Result<()> {
+        writeln!(self.out, "{}:", self.source_label(&name))?;
+        self.stmt(node)
+    }
+
+    fn goto(&mut self, name: String) -> Result<()> {
+        writeln!(self.out, "  jmp {}", self.source_label(&name))?;
+        Ok(())
+    }
+
+    fn break_n(&mut self) -> Result<()> {
+        let label = self.breaks.last().expect("break out of a loop");
+        writeln!(self.out, "  jmp {}", label)?;
+        Ok(())
+    }
+
+    fn continue_n(&mut self) -> Result<()> {
+        let label = self.continues.last().expect("continue out of a loop");
+        writeln!(self.out, "  jmp {}", label)?;
+        Ok(())
+    }
+
     fn for_n(
         &mut self,
         condition1: Option<Node>,
@@ -60,154 +313,581 @@ impl Codegen {
         self.block_index += 1;
 
         if let Some(cdn) = condition1 {
-            self.node(cdn)?;
+            self.stmt(cdn)?;
         }
-        println!(".Lbegin{}:", block_index);
+        writeln!(self.out, ".Lbegin{}:", block_index)?;
+        // the loop without the condition runs until break
         if let Some(cdn) = condition2 {
-            self.node(cdn)?;
+            self.expr(cdn)?;
+            writeln!(self.out, "  pop rax")?;
+            writeln!(self.out, "  cmp rax, 0")?;
+            writeln!(self.out, "  je .Lend{}", block_index)?;
         }
-
-        println!("  pop rax");
-        println!("  cmp rax, 0");
-        println!("  je .Lend{}", block_index);
+        self.loop_body(body, block_index, format!(".Lcontinue{}", block_index))?;
+        writeln!(self.out, ".Lcontinue{}:", block_index)?;
         if let Some(cdn) = condition3 {
-            self.node(cdn)?;
+            self.stmt(cdn)?;
         }
-        self.node(body)?;
-        println!("  jmp .Lbegin{}", block_index);
-        println!(".Lend{}:", block_index);
+        writeln!(self.out, "  jmp .Lbegin{}", block_index)?;
+        writeln!(self.out, ".Lend{}:", block_index)?;
 
         Ok(())
     }
 
+    fn call(&mut self, name: String, args: Vec<Node>, ty: Type) -> Result<()> {
+        let n_args = args.len();
+        let n_stack_args = n_args.saturating_sub(ARGUMENT_REGISTERS.len());
+
+        // rsp has to be 16 byte aligned at `call`.
+        // The stack machine leaves rsp at an arbitrary multiple of 8,
+        // so decide the padding at runtime and keep it on the stack
+        // (below the stack arguments) to drop it after the call.
+        writeln!(self.out, "  mov rax, rsp")?;
+        writeln!(self.out, "  sub rax, {}", 8 * (n_stack_args + 1))?;
+        writeln!(self.out, "  and rax, 15")?;
+        writeln!(self.out, "  sub rsp, rax")?;
+        writeln!(self.out, "  push rax")?;
+
+        // push from the last argument so that the 7th argument and later
+        // are left on the stack in the order the callee expects
+        for arg in args.into_iter().rev() {
+            self.expr(arg)?;
+        }
+        for register in ARGUMENT_REGISTERS.iter().take(n_args) {
+            writeln!(self.out, "  pop {}", register)?;
+        }
+
+        // al holds the number of vector registers used by a variadic call
+        writeln!(self.out, "  mov rax, 0")?;
+        writeln!(self.out, "  call {}", name)?;
+        if n_stack_args > 0 {
+            writeln!(self.out, "  add rsp, {}", 8 * n_stack_args)?;
+        }
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  add rsp, rdi")?;
+        // only the lower bits of rax are defined for a return value narrower than 64 bits
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn function(
+        &mut self,
+        name: String,
+        params: Vec<Node>,
+        body: Option<Node>,
+        frame_size: usize,
+    ) -> Result<()> {
+        let body = match body {
+            Some(body) => body,
+            // only declared, defined in another object
+            None => return Ok(()),
+        };
+        writeln!(self.out, ".globl {}", name)?;
+        writeln!(self.out, "{}:", name)?;
+        self.function = name;
+        self.prologue(frame_size)?;
+
+        for (i, param) in params.into_iter().enumerate() {
+            if let NodeKind::LocalVariable(_, offset, ty) = param.kind {
+                if i < ARGUMENT_REGISTERS.len() {
+                    writeln!(
+                        self.out,
+                        "  mov [rbp-{}], {}",
+                        offset,
+                        argument_register(i, &ty)
+                    )?;
+                } else {
+                    // [rbp+8] is the return address, stack arguments start from [rbp+16]
+                    let i = i - ARGUMENT_REGISTERS.len();
+                    writeln!(self.out, "  mov rax, [rbp+{}]", 16 + 8 * i)?;
+                    writeln!(self.out, "  mov [rbp-{}], {}", offset, rax(&ty))?;
+                }
+            } else {
+                unreachable!();
+            }
+        }
+
+        // the end of a body which ends with return is never reached
+        let returns = match &body.kind {
+            NodeKind::Block(nodes) => {
+                matches!(nodes.last().map(|n| &n.kind), Some(NodeKind::Return(_)))
+            }
+            _ => false,
+        };
+        self.stmt(body)?;
+        if returns {
+            return Ok(());
+        }
+        // reaching the end of main returns 0
+        if self.function == "main" {
+            writeln!(self.out, "  mov rax, 0")?;
+        }
+        self.epilogue()
+    }
+
+    /// an initialized variable is placed in .data and the others in .bss,
+    /// which is filled with 0 when the program starts
+    fn global(&mut self, name: String, ty: Type, init: Option<Node>) -> Result<()> {
+        let section = if init.is_some() { ".data" } else { ".bss" };
+        writeln!(self.out, "  {}", section)?;
+        writeln!(self.out, ".globl {}", name)?;
+        writeln!(self.out, "  .p2align {}", ty.align().trailing_zeros())?;
+        writeln!(self.out, "{}:", name)?;
+        match init {
+            Some(init) => self.data(init)?,
+            None => writeln!(self.out, "  .zero {}", ty.size())?,
+        }
+        writeln!(self.out, "  .text")?;
+        Ok(())
+    }
+
+    /// the constant in .data,
+    /// the linker fills the address of a symbol
+    fn data(&mut self, n: Node) -> Result<()> {
+        let ty = expect_ty(n.ty);
+        match n.kind {
+            NodeKind::Number(n) => writeln!(self.out, "  {} {}", data_directive(&ty), n)?,
+            NodeKind::Address(object) => {
+                let symbol = self.symbol(*object)?;
+                writeln!(self.out, "  .quad {}", symbol)?;
+            }
+            NodeKind::Plus(address, offset) => match (address.kind, offset.kind) {
+                (NodeKind::Address(object), NodeKind::Number(offset)) => {
+                    let symbol = self.symbol(*object)?;
+                    writeln!(self.out, "  .quad {}{:+}", symbol, offset)?;
+                }
+                kind => unreachable!("{:?} is not an address constant", kind),
+            },
+            NodeKind::InitializerList(elements) => {
+                let mut size = 0;
+                for element in elements {
+                    size += element.ty().size();
+                    self.data(element)?;
+                }
+                if size < ty.size() {
+                    writeln!(self.out, "  .zero {}", ty.size() - size)?;
+                }
+            }
+            kind => unreachable!("{:?} is not a constant", kind),
+        }
+        Ok(())
+    }
+
+    /// the symbol of the object whose address is in .data
+    fn symbol(&mut self, n: Node) -> Result<String> {
+        match n.kind {
+            NodeKind::GlobalVariable(name, _) => Ok(name),
+            NodeKind::Str(s) => {
+                let label = self.literal(s)?;
+                writeln!(self.out, "  .data")?;
+                Ok(label)
+            }
+            kind => unreachable!("{:?} has no symbol", kind),
+        }
+    }
+
     fn block(&mut self, nodes: Vec<Node>) -> Result<()> {
         for node in nodes.into_iter() {
-            self.node(node)?;
+            self.stmt(node)?;
+        }
+        Ok(())
+    }
+
+    /// a statement leaves the stack as it was
+    fn stmt(&mut self, n: Node) -> Result<()> {
+        match n.kind {
+            NodeKind::Return(n) => self.return_n(*n)?,
+            NodeKind::If(condition, then, else_body) => {
+                self.if_n(*condition, *then, else_body.map(|e| *e))?
+            }
+            NodeKind::While(condition, body) => self.whlie_n(*condition, *body)?,
+            NodeKind::For(condition1, condition2, condition3, body) => self.for_n(
+                condition1.map(|c| *c),
+                condition2.map(|c| *c),
+                condition3.map(|c| *c),
+                *body,
+            )?,
+            NodeKind::DoWhile(body, condition) => self.do_while(*body, *condition)?,
+            NodeKind::Switch(condition, body, cases, default) => {
+                self.switch(*condition, *body, cases, default)?
+            }
+            NodeKind::Case(value, node) => self.case(*value, *node)?,
+            NodeKind::Default(node) => self.default(*node)?,
+            NodeKind::Label(name, node) => self.label(name, *node)?,
+            NodeKind::Goto(name) => self.goto(name)?,
+            NodeKind::Break => self.break_n()?,
+            NodeKind::Continue => self.continue_n()?,
+            NodeKind::Block(nodes) => self.block(nodes)?,
+            // the value of the expression statement is discarded
+            kind => {
+                self.expr(Node { kind, ..n })?;
+                writeln!(self.out, "  pop rax")?;
+            }
         }
         Ok(())
     }
 
     fn definition_variable(&mut self, _id: String, offset: usize) -> Result<()> {
-        println!("  mov rax, rbp");
-        println!("  sub rax, {}", offset);
-        println!("  push rax");
+        writeln!(self.out, "  mov rax, rbp")?;
+        writeln!(self.out, "  sub rax, {}", offset)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    /// push the address which the lvalue points to
+    fn address(&mut self, n: Node) -> Result<()> {
+        match n.kind {
+            NodeKind::LocalVariable(id, offset, _) => self.definition_variable(id, offset),
+            NodeKind::GlobalVariable(name, _) => self.global_address(name),
+            NodeKind::Str(s) => self.string(s),
+            // the value of the pointer is the address
+            NodeKind::Dereference(n) => self.expr(*n),
+            _ => unreachable!("expect lvalue"),
+        }
+    }
+
+    /// the string literal in .rodata, which is left as the current section.
+    /// returns the label of it
+    fn literal(&mut self, s: Vec<u8>) -> Result<String> {
+        let block_index = self.block_index;
+        self.block_index += 1;
+
+        let bytes: Vec<String> = s.iter().chain(&[0]).map(|c| c.to_string()).collect();
+        writeln!(self.out, "  .section .rodata")?;
+        writeln!(self.out, ".Lstr{}:", block_index)?;
+        writeln!(self.out, "  .byte {}", bytes.join(", "))?;
+        Ok(format!(".Lstr{}", block_index))
+    }
+
+    fn string(&mut self, s: Vec<u8>) -> Result<()> {
+        let label = self.literal(s)?;
+        writeln!(self.out, "  .text")?;
+        writeln!(self.out, "  lea rax, [rip + {}]", label)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    /// replace the address on the top of the stack with the value of the type there.
+    /// the value is extended to 64 bits
+    fn load(&mut self, ty: &Type) -> Result<()> {
+        writeln!(self.out, "  pop rax")?;
+        match (ty.size(), ty.is_signed()) {
+            (1, _) => writeln!(self.out, "  movsx rax, byte ptr [rax]")?,
+            (2, _) => writeln!(self.out, "  movsx rax, word ptr [rax]")?,
+            (4, true) => writeln!(self.out, "  movsxd rax, dword ptr [rax]")?,
+            (4, false) => writeln!(self.out, "  mov eax, dword ptr [rax]")?,
+            _ => writeln!(self.out, "  mov rax, [rax]")?,
+        }
+        writeln!(self.out, "  push rax")?;
         Ok(())
     }
 
-    fn local_val(&mut self, id: String, offset: usize) -> Result<()> {
+    /// store rdi to the address in rax in the width of the type
+    fn store(&mut self, ty: &Type) -> Result<()> {
+        writeln!(self.out, "  mov [rax], {}", argument_register(0, ty))?;
+        Ok(())
+    }
+
+    /// extend the lower bits of rax which the type occupies,
+    /// with the sign bit for a signed type and with 0 for an unsigned type
+    fn extend(&mut self, ty: &Type) -> Result<()> {
+        match (ty.size(), ty.is_signed()) {
+            (1, _) => writeln!(self.out, "  movsx rax, al")?,
+            (2, _) => writeln!(self.out, "  movsx rax, ax")?,
+            (4, true) => writeln!(self.out, "  movsxd rax, eax")?,
+            // writing a 32 bit register clears the upper bits
+            (4, false) => writeln!(self.out, "  mov eax, eax")?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn local_val(&mut self, id: String, offset: usize, ty: Type) -> Result<()> {
         self.definition_variable(id, offset)?;
-        println!("  pop rax");
-        println!("  mov rax, [rax]");
-        println!("  push rax");
+        self.load(&ty)
+    }
+
+    /// the address relative to rip works wherever the executable is loaded
+    fn global_address(&mut self, name: String) -> Result<()> {
+        writeln!(self.out, "  lea rax, [rip + {}]", name)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn global_val(&mut self, name: String, ty: Type) -> Result<()> {
+        self.global_address(name)?;
+        self.load(&ty)
+    }
+
+    fn dereference(&mut self, n: Node, ty: Type) -> Result<()> {
+        self.expr(n)?;
+        self.load(&ty)
+    }
+
+    fn cast(&mut self, n: Node, ty: Type) -> Result<()> {
+        self.expr(n)?;
+        writeln!(self.out, "  pop rax")?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
         Ok(())
     }
 
     fn assign(&mut self, left: Node, right: Node) -> Result<()> {
-        if let Node::LocalVariable(id, offset) = left {
-            self.definition_variable(id, offset)?;
-        } else {
-            unreachable!();
-        }
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  mov [rax], rdi");
-        println!("  push rdi");
+        let ty = left.ty().clone();
+        self.address(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        self.store(&ty)?;
+        writeln!(self.out, "  push rdi")?;
         Ok(())
     }
 
-    fn return_n(&mut self, n: Node) -> Result<()> {
-        self.node(n)?;
-        println!("  pop rax");
-        println!("  mov rsp, rbp");
-        println!("  pop rbp");
-        println!("  ret");
+    /// the address of the target stays on the stack while the operation is evaluated
+    /// so that Stored in it can load the value
+    fn compound_assign(&mut self, target: Node, operation: Node) -> Result<()> {
+        let ty = target.ty().clone();
+        self.address(target)?;
+        self.expr(operation)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        self.store(&ty)?;
+        writeln!(self.out, "  push rdi")?;
+        Ok(())
+    }
+
+    fn post_assign(&mut self, target: Node, operation: Node) -> Result<()> {
+        let ty = target.ty().clone();
+        self.address(target)?;
+        // the value before the assignment is kept under the address
+        writeln!(self.out, "  mov rax, [rsp]")?;
+        writeln!(self.out, "  push rax")?;
+        self.load(&ty)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  push rdi")?;
+        writeln!(self.out, "  push rax")?;
+        self.expr(operation)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        self.store(&ty)?;
         Ok(())
     }
 
+    /// the address of the target is on the top of the stack
+    fn stored(&mut self, ty: Type) -> Result<()> {
+        writeln!(self.out, "  mov rax, [rsp]")?;
+        writeln!(self.out, "  push rax")?;
+        self.load(&ty)
+    }
+
+    fn return_n(&mut self, n: Node) -> Result<()> {
+        self.expr(n)?;
+        writeln!(self.out, "  pop rax")?;
+        self.epilogue()
+    }
+
     fn equal(&mut self, left: Node, right: Node) -> Result<()> {
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  cmp rax, rdi");
+        let (ax, di) = registers(left.ty());
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp {}, {}", ax, di)?;
         // sete 命令
         // cmp命令での結果を指定したレジスタにコピーする
-        println!("  sete al");
+        writeln!(self.out, "  sete al")?;
         // sete命令が8bitレジスタにしか扱えないので
         // movzb命令で上位56bitをゼロクリアして持ってくる
-        println!("  movzb rax, al");
-        println!("  push rax");
+        writeln!(self.out, "  movzb rax, al")?;
+        writeln!(self.out, "  push rax")?;
 
         Ok(())
     }
 
     fn unequal(&mut self, left: Node, right: Node) -> Result<()> {
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  cmp rax, rdi");
-        println!("  setne al");
-        println!("  movzb rax, al");
-        println!("  push rax");
+        let (ax, di) = registers(left.ty());
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp {}, {}", ax, di)?;
+        writeln!(self.out, "  setne al")?;
+        writeln!(self.out, "  movzb rax, al")?;
+        writeln!(self.out, "  push rax")?;
         Ok(())
     }
 
+    /// unsigned values and pointers are compared by setb and setbe
     fn less(&mut self, left: Node, right: Node) -> Result<()> {
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  cmp rax, rdi");
-        println!("  setl al");
-        println!("  movzb rax, al");
-        println!("  push rax");
+        let (ax, di) = registers(left.ty());
+        let set = if left.ty().is_signed() {
+            "setl"
+        } else {
+            "setb"
+        };
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp {}, {}", ax, di)?;
+        writeln!(self.out, "  {} al", set)?;
+        writeln!(self.out, "  movzb rax, al")?;
+        writeln!(self.out, "  push rax")?;
         Ok(())
     }
     fn less_equal(&mut self, left: Node, right: Node) -> Result<()> {
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  cmp rax, rdi");
-        println!("  setle al");
-        println!("  movzb rax, al");
-        println!("  push rax");
+        let (ax, di) = registers(left.ty());
+        let set = if left.ty().is_signed() {
+            "setle"
+        } else {
+            "setbe"
+        };
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp {}, {}", ax, di)?;
+        writeln!(self.out, "  {} al", set)?;
+        writeln!(self.out, "  movzb rax, al")?;
+        writeln!(self.out, "  push rax")?;
         Ok(())
     }
 
-    fn plus(&mut self, left: Node, right: Node) -> Result<()> {
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  add rax, rdi");
-        println!("  push rax");
+    /// jumps to .Lfalse{n} when either operand is 0 and skips the rest
+    fn and(&mut self, left: Node, right: Node) -> Result<()> {
+        let block_index = self.block_index;
+        self.block_index += 1;
+
+        for n in [left, right] {
+            self.expr(n)?;
+            writeln!(self.out, "  pop rax")?;
+            writeln!(self.out, "  cmp rax, 0")?;
+            writeln!(self.out, "  je .Lfalse{}", block_index)?;
+        }
+        writeln!(self.out, "  push 1")?;
+        writeln!(self.out, "  jmp .Lend{}", block_index)?;
+        writeln!(self.out, ".Lfalse{}:", block_index)?;
+        writeln!(self.out, "  push 0")?;
+        writeln!(self.out, ".Lend{}:", block_index)?;
         Ok(())
     }
 
-    fn minus(&mut self, left: Node, right: Node) -> Result<()> {
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  sub rax, rdi");
-        println!("  push rax");
+    /// jumps to .Ltrue{n} when either operand is not 0 and skips the rest
+    fn or(&mut self, left: Node, right: Node) -> Result<()> {
+        let block_index = self.block_index;
+        self.block_index += 1;
+
+        for n in [left, right] {
+            self.expr(n)?;
+            writeln!(self.out, "  pop rax")?;
+            writeln!(self.out, "  cmp rax, 0")?;
+            writeln!(self.out, "  jne .Ltrue{}", block_index)?;
+        }
+        writeln!(self.out, "  push 0")?;
+        writeln!(self.out, "  jmp .Lend{}", block_index)?;
+        writeln!(self.out, ".Ltrue{}:", block_index)?;
+        writeln!(self.out, "  push 1")?;
+        writeln!(self.out, ".Lend{}:", block_index)?;
         Ok(())
     }
 
-    fn multiple(&mut self, left: Node, right: Node) -> Result<()> {
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  imul rax, rdi");
-        println!("  push rax");
+    fn not(&mut self, n: Node) -> Result<()> {
+        self.expr(n)?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  cmp rax, 0")?;
+        writeln!(self.out, "  sete al")?;
+        writeln!(self.out, "  movzb rax, al")?;
+        writeln!(self.out, "  push rax")?;
         Ok(())
     }
 
-    fn devide(&mut self, left: Node, right: Node) -> Result<()> {
+    fn negate(&mut self, n: Node, ty: Type) -> Result<()> {
+        self.expr(n)?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  neg {}", registers(&ty).0)?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn bit_not(&mut self, n: Node, ty: Type) -> Result<()> {
+        self.expr(n)?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  not {}", registers(&ty).0)?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn plus(&mut self, left: Node, right: Node, ty: Type) -> Result<()> {
+        let (left_ty, right_ty) = (left.ty().clone(), right.ty().clone());
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        // pointer + n moves the pointer by n elements
+        match (left_ty, right_ty) {
+            (Type::Pointer(to), _) => {
+                writeln!(self.out, "  imul rdi, rdi, {}", to.size())?;
+                writeln!(self.out, "  add rax, rdi")?;
+            }
+            (_, Type::Pointer(to)) => {
+                writeln!(self.out, "  imul rax, rax, {}", to.size())?;
+                writeln!(self.out, "  add rax, rdi")?;
+            }
+            _ => {
+                let (ax, di) = registers(&ty);
+                writeln!(self.out, "  add {}, {}", ax, di)?;
+                self.extend(&ty)?;
+            }
+        }
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn minus(&mut self, left: Node, right: Node, ty: Type) -> Result<()> {
+        let (left_ty, right_ty) = (left.ty().clone(), right.ty().clone());
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        match (left_ty, right_ty) {
+            // pointer - pointer is the number of elements between them
+            (Type::Pointer(to), Type::Pointer(_)) => {
+                writeln!(self.out, "  sub rax, rdi")?;
+                writeln!(self.out, "  mov rdi, {}", to.size())?;
+                writeln!(self.out, "  cqo")?;
+                writeln!(self.out, "  idiv rdi")?;
+            }
+            (Type::Pointer(to), _) => {
+                writeln!(self.out, "  imul rdi, rdi, {}", to.size())?;
+                writeln!(self.out, "  sub rax, rdi")?;
+            }
+            _ => {
+                let (ax, di) = registers(&ty);
+                writeln!(self.out, "  sub {}, {}", ax, di)?;
+                self.extend(&ty)?;
+            }
+        }
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn multiple(&mut self, left: Node, right: Node, ty: Type) -> Result<()> {
+        let (ax, di) = registers(&ty);
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  imul {}, {}", ax, di)?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn devide(&mut self, left: Node, right: Node, ty: Type) -> Result<()> {
         /*
          * idivは符号あり除算を行う命令です。
          * x86-64のidivが素直な仕様になっていれば、
@@ -220,73 +900,378 @@ impl Codegen {
          * 商をRAXに、余りをRDXにセットする、という仕様になっています。
          * cqo命令を使うと、RAXに入っている64ビットの値を128ビットに伸ばしてRDXとRAXにセットすることができるので、
          * 上記のコードではidivを呼ぶ前にcqoを呼んでいます。
+         * intの場合はcdq命令でEAXをEDX:EAXに伸ばして32ビットで割ります。
+         * 符号なしの場合はRDXを0にしてdivで割ります。
          */
-        self.node(left)?;
-        self.node(right)?;
-        println!("  pop rdi");
-        println!("  pop rax");
-        println!("  cqo");
-        println!("  idiv rdi");
-        println!("  push rax");
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        self.division(&ty)?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
         Ok(())
     }
 
-    fn node(&mut self, n: Node) -> Result<()> {
-        match n {
-            Node::Number(n) => {
-                println!("  push {}", n);
+    /// the remainder which the division leaves in rdx
+    fn modulo(&mut self, left: Node, right: Node, ty: Type) -> Result<()> {
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        self.division(&ty)?;
+        writeln!(self.out, "  mov rax, rdx")?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    /// divide rax by rdi in the type, the quotient is in rax and the remainder is in rdx
+    fn division(&mut self, ty: &Type) -> Result<()> {
+        let (_, di) = registers(ty);
+        match (ty.size(), ty.is_signed()) {
+            (8, true) => writeln!(self.out, "  cqo")?,
+            (_, true) => writeln!(self.out, "  cdq")?,
+            (_, false) => writeln!(self.out, "  mov edx, 0")?,
+        }
+        let op = if ty.is_signed() { "idiv" } else { "div" };
+        writeln!(self.out, "  {} {}", op, di)?;
+        Ok(())
+    }
+
+    /// and, or and xor
+    fn bitwise(&mut self, left: Node, right: Node, ty: Type, op: &str) -> Result<()> {
+        let (ax, di) = registers(&ty);
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rdi")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  {} {}, {}", op, ax, di)?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    /// the count is taken from cl
+    fn shift(&mut self, left: Node, right: Node, ty: Type, op: &str) -> Result<()> {
+        self.expr(left)?;
+        self.expr(right)?;
+        writeln!(self.out, "  pop rcx")?;
+        writeln!(self.out, "  pop rax")?;
+        writeln!(self.out, "  {} {}, cl", op, registers(&ty).0)?;
+        self.extend(&ty)?;
+        writeln!(self.out, "  push rax")?;
+        Ok(())
+    }
+
+    fn shift_left(&mut self, left: Node, right: Node, ty: Type) -> Result<()> {
+        self.shift(left, right, ty, "shl")
+    }
+
+    /// arithmetic shift for signed values, logical shift for unsigned ones
+    fn shift_right(&mut self, left: Node, right: Node, ty: Type) -> Result<()> {
+        let op = if ty.is_signed() { "sar" } else { "shr" };
+        self.shift(left, right, ty, op)
+    }
+
+    /// an expression pushes its value
+    fn expr(&mut self, n: Node) -> Result<()> {
+        let ty = n.ty;
+        match n.kind {
+            NodeKind::Number(n) => {
+                writeln!(self.out, "  mov rax, {}", n)?;
+                writeln!(self.out, "  push rax")?;
             }
-            Node::Equal(left, right) => self.equal(*left, *right)?,
-            Node::UnEqual(left, right) => self.unequal(*left, *right)?,
-            Node::Less(left, right) => self.less(*left, *right)?,
-            Node::LessEqual(left, right) => self.less_equal(*left, *right)?,
-            Node::Plus(left, right) => self.plus(*left, *right)?,
-            Node::Minus(left, right) => self.minus(*left, *right)?,
-            Node::Multiple(left, right) => self.multiple(*left, *right)?,
-            Node::Devide(left, right) => self.devide(*left, *right)?,
-            Node::Assign(left, right) => self.assign(*left, *right)?,
-            Node::LocalVariable(id, offset) => self.local_val(id, offset)?,
-            Node::Return(n) => self.return_n(*n)?,
-            Node::If(condition, then, else_body) => {
-                self.if_n(*condition, *then, else_body.map(|e| *e))?
+            NodeKind::Equal(left, right) => self.equal(*left, *right)?,
+            NodeKind::UnEqual(left, right) => self.unequal(*left, *right)?,
+            NodeKind::Less(left, right) => self.less(*left, *right)?,
+            NodeKind::LessEqual(left, right) => self.less_equal(*left, *right)?,
+            NodeKind::Plus(left, right) => self.plus(*left, *right, expect_ty(ty))?,
+            NodeKind::Minus(left, right) => self.minus(*left, *right, expect_ty(ty))?,
+            NodeKind::Multiple(left, right) => self.multiple(*left, *right, expect_ty(ty))?,
+            NodeKind::Devide(left, right) => self.devide(*left, *right, expect_ty(ty))?,
+            NodeKind::Modulo(left, right) => self.modulo(*left, *right, expect_ty(ty))?,
+            NodeKind::BitAnd(left, right) => self.bitwise(*left, *right, expect_ty(ty), "and")?,
+            NodeKind::BitOr(left, right) => self.bitwise(*left, *right, expect_ty(ty), "or")?,
+            NodeKind::BitXor(left, right) => self.bitwise(*left, *right, expect_ty(ty), "xor")?,
+            NodeKind::ShiftLeft(left, right) => self.shift_left(*left, *right, expect_ty(ty))?,
+            NodeKind::ShiftRight(left, right) => self.shift_right(*left, *right, expect_ty(ty))?,
+            NodeKind::And(left, right) => self.and(*left, *right)?,
+            NodeKind::Or(left, right) => self.or(*left, *right)?,
+            NodeKind::Not(n) => self.not(*n)?,
+            NodeKind::Conditional(condition, then, else_n) => {
+                self.conditional(*condition, *then, *else_n)?
             }
-            Node::While(condition, body) => self.whlie_n(*condition, *body)?,
-            Node::For(condition1, condition2, condition3, body) => self.for_n(
-                condition1.map(|c| *c),
-                condition2.map(|c| *c),
-                condition3.map(|c| *c),
-                *body,
-            )?,
-            Node::Block(nodes) => self.block(*nodes)?,
+            NodeKind::Comma(left, right) => self.comma(*left, *right)?,
+            NodeKind::Negate(n) => self.negate(*n, expect_ty(ty))?,
+            NodeKind::BitNot(n) => self.bit_not(*n, expect_ty(ty))?,
+            NodeKind::Assign(left, right) => self.assign(*left, *right)?,
+            NodeKind::CompoundAssign(target, operation) => {
+                self.compound_assign(*target, *operation)?
+            }
+            NodeKind::PostAssign(target, operation) => self.post_assign(*target, *operation)?,
+            NodeKind::Stored => self.stored(expect_ty(ty))?,
+            NodeKind::LocalVariable(id, offset, ty) => self.local_val(id, offset, ty)?,
+            NodeKind::GlobalVariable(name, ty) => self.global_val(name, ty)?,
+            NodeKind::Address(n) => self.address(*n)?,
+            NodeKind::Dereference(n) => self.dereference(*n, expect_ty(ty))?,
+            NodeKind::Cast(n) => self.cast(*n, expect_ty(ty))?,
+            NodeKind::Call(name, args) => self.call(name, args, expect_ty(ty))?,
+            kind => unreachable!("{:?} is not an expression", kind),
         }
         Ok(())
     }
 
-    fn prologue(&mut self) -> Result<()> {
-        // allocate for 26 variable argument
-        println!("  push rbp");
-        println!("  mov rbp, rsp");
-        println!("  sub rsp, 208");
+    fn prologue(&mut self, frame_size: usize) -> Result<()> {
+        writeln!(self.out, "  push rbp")?;
+        writeln!(self.out, "  mov rbp, rsp")?;
+        writeln!(self.out, "  sub rsp, {}", frame_size)?;
+        Ok(())
+    }
+
+    fn epilogue(&mut self) -> Result<()> {
+        writeln!(self.out, "  mov rsp, rbp")?;
+        writeln!(self.out, "  pop rbp")?;
+        writeln!(self.out, "  ret")?;
         Ok(())
     }
 
     pub fn gen(&mut self, nodes: Vec<Node>) -> Result<()> {
-        println!(".intel_syntax noprefix");
-        println!(".globl main");
-        println!("main:");
-        self.prologue()?;
+        writeln!(self.out, ".intel_syntax noprefix")?;
         for n in nodes {
-            self.node(n)?;
-            println!("  pop rax");
+            match n.kind {
+                NodeKind::Function(name, _, params, _, body, frame_size) => {
+                    self.function(name, params, body.map(|b| *b), frame_size)?
+                }
+                NodeKind::GlobalDefinition(name, ty, init) => {
+                    self.global(name, ty, init.map(|i| *i))?
+                }
+                kind => unreachable!("{:?} is not at file scope", kind),
+            }
         }
-        println!("  mov rsp, rbp");
-        println!("  pop rbp");
-        println!("  ret");
+        // the stack does not have to be executable
+        writeln!(self.out, "  .section .note.GNU-stack,\"\",@progbits")?;
         Ok(())
     }
 }
 
-pub fn codegen(nodes: Vec<Node>) -> Result<()> {
-    let mut c = Codegen::new();
-    c.gen(nodes)
+pub fn codegen<W: Write>(nodes: Vec<Node>, out: W) -> Result<()> {
+    let mut c = Codegen::new(out);
+    c.gen(nodes)?;
+    c.out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::parse;
+    use super::super::sema::Checker;
+    use super::super::tokenizer::tokenize;
+    use super::*;
+
+    fn asm(src: &str) -> String {
+        let (tokens, spans) = tokenize(src, 0).unwrap();
+        let nodes = Checker::new().program(parse(&tokens, &spans).0).unwrap();
+        let mut out = vec![];
+        codegen(nodes, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn it_function() {
+        assert_eq!(
+            asm("int main() { return 42; }"),
+            ".intel_syntax noprefix
+.globl main
+main:
+  push rbp
+  mov rbp, rsp
+  sub rsp, 0
+  mov rax, 42
+  push rax
+  pop rax
+  mov rsp, rbp
+  pop rbp
+  ret
+  .section .note.GNU-stack,\"\",@progbits
+"
+        );
+        // a prototype emits nothing
+        assert_eq!(
+            asm("int f(int a);"),
+            ".intel_syntax noprefix\n  .section .note.GNU-stack,\"\",@progbits\n"
+        );
+    }
+
+    #[test]
+    fn it_stmt() {
+        // reaching the end of main returns 0, but not the end of another function
+        let out = asm("int f() { 1; } int main() { int x; x = 3; if (x) x; }");
+        assert_eq!(out.matches("  mov rax, 0\n  mov rsp, rbp\n").count(), 1);
+        assert!(out.contains("  pop rax\n.Lend0:\n  mov rax, 0\n  mov rsp, rbp\n"));
+        // the value of an expression statement is popped
+        assert!(out.contains(
+            "f:\n  push rbp\n  mov rbp, rsp\n  sub rsp, 0\n  mov rax, 1\n  push rax\n  pop rax\n"
+        ));
+    }
+
+    #[test]
+    fn it_array() {
+        let out = asm("int main() { int a[3][4]; long b[2]; a[2][1] = 5; return b[1]; }");
+        // a takes 48 bytes and b is placed under it
+        assert!(out.contains("  sub rsp, 64\n"));
+        assert!(out.contains("  mov rax, rbp\n  sub rax, 64\n"));
+        // a row of a is 16 bytes
+        assert!(out.contains("  imul rdi, rdi, 16\n"));
+        assert!(out.contains("  imul rdi, rdi, 4\n"));
+        assert!(out.contains("  imul rdi, rdi, 8\n"));
+    }
+
+    #[test]
+    fn it_string() {
+        let out = asm(r#"int main() { char *s; s = "a\n"; s = "a\n"; }"#);
+        // every literal has its own label
+        assert!(out.contains(
+            "  .section .rodata\n.Lstr0:\n  .byte 97, 10, 0\n  .text\n  lea rax, [rip + .Lstr0]\n  push rax\n"
+        ));
+        assert!(out.contains(".Lstr1:\n"));
+    }
+
+    #[test]
+    fn it_unsigned() {
+        let out = asm("int main() { return 7u / 2 + 7ul % 2 + (1 < 2u) + (0xffffffff <= 1); }");
+        assert!(out.contains("  mov edx, 0\n  div edi\n  mov eax, eax\n"));
+        assert!(out.contains("  mov edx, 0\n  div rdi\n  mov rax, rdx\n"));
+        assert!(out.contains("  setb al\n"));
+        assert!(out.contains("  setbe al\n"));
+    }
+
+    #[test]
+    fn it_global() {
+        let out = asm("short s = -2; long a[3]; int main() { return s + a[1]; }");
+        assert!(out.contains("  .data\n.globl s\n  .p2align 1\ns:\n  .short -2\n  .text\n"));
+        assert!(out.contains("  .bss\n.globl a\n  .p2align 3\na:\n  .zero 24\n  .text\n"));
+        assert!(out.contains(
+            "  lea rax, [rip + s]\n  push rax\n  pop rax\n  movsx rax, word ptr [rax]\n"
+        ));
+        assert!(out.contains("  lea rax, [rip + a]\n"));
+    }
+
+    #[test]
+    fn it_initializer() {
+        let out = asm(
+            "int a[4] = {1, 2}; int *p = a + 1; char *s[] = {\"hi\", 0}; int main() { return 0; }",
+        );
+        assert!(out.contains("a:\n  .long 1\n  .long 2\n  .zero 8\n  .text\n"));
+        assert!(out.contains("p:\n  .quad a+4\n  .text\n"));
+        assert!(out.contains(
+            "s:\n  .section .rodata\n.Lstr0:\n  .byte 104, 105, 0\n  .data\n  .quad .Lstr0\n  .quad 0\n  .text\n"
+        ));
+    }
+
+    #[test]
+    fn it_width() {
+        let out = asm("int main() { char c; short s; long l; c = 1; s = c; l = s; return c / s; }");
+        assert!(out.contains("  mov [rax], dil\n"));
+        assert!(out.contains("  mov [rax], di\n"));
+        assert!(out.contains("  mov [rax], rdi\n"));
+        assert!(out.contains("  movsx rax, byte ptr [rax]\n"));
+        assert!(out.contains("  movsx rax, word ptr [rax]\n"));
+        assert!(out.contains("  cdq\n  idiv edi\n"));
+    }
+
+    #[test]
+    fn it_call() {
+        let out = asm(
+            "int f(int a, int b, int c, int d, int e, int f, int g) { return g; }
+            int main() { return f(1, 2, 3, 4, 5, 6, 7); }",
+        );
+        // the 7th parameter is on the stack of the caller
+        assert!(out.contains("  mov rax, [rbp+16]\n  mov [rbp-28], eax\n"));
+        assert!(out.contains("  call f\n  add rsp, 8\n  pop rdi\n  add rsp, rdi\n"));
+    }
+
+    #[test]
+    fn it_logical() {
+        let out = asm("int main() { int *p; p = 0; return p && *p; }");
+        // *p is skipped when p is 0
+        assert!(out.contains("  cmp rax, 0\n  je .Lfalse0\n  mov rax, rbp\n"));
+        assert!(out.contains("  push 1\n  jmp .Lend0\n.Lfalse0:\n  push 0\n.Lend0:\n"));
+        let out = asm("int main() { return 0 || !1; }");
+        assert!(out.contains("  jne .Ltrue0\n"));
+        assert!(out.contains("  sete al\n"));
+    }
+
+    #[test]
+    fn it_bitwise() {
+        let out =
+            asm("int main() { long l; int i; l = 7; i = 3; return l % i + (i >> 1) + (i << l); }");
+        assert!(out.contains("  cqo\n  idiv rdi\n  mov rax, rdx\n"));
+        assert!(out.contains("  sar eax, cl\n"));
+        assert!(out.contains("  shl eax, cl\n"));
+        let out = asm("int main() { return 6 & 3 | 1 ^ 4; }");
+        assert!(out.contains("  and eax, edi\n"));
+        assert!(out.contains("  xor eax, edi\n"));
+        assert!(out.contains("  or eax, edi\n"));
+    }
+
+    #[test]
+    fn it_compound_assign() {
+        // the address of *f() is evaluated once
+        let out = asm("int *f(); int main() { *f() += 1; return 0; }");
+        assert_eq!(out.matches("  call f\n").count(), 1);
+        assert!(out
+            .contains("  mov rax, [rsp]\n  push rax\n  pop rax\n  movsxd rax, dword ptr [rax]\n"));
+        let out = asm("int *f(); int main() { (*f())++; return 0; }");
+        assert_eq!(out.matches("  call f\n").count(), 1);
+    }
+
+    #[test]
+    fn it_conditional() {
+        let out = asm("int main() { int i; return i ? 1 : 2, 3; }");
+        assert!(out.contains(
+            "  je .Lelse0\n  mov rax, 1\n  push rax\n  jmp .Lend0\n.Lelse0:\n  mov rax, 2\n  push rax\n.Lend0:\n  pop rax\n"
+        ));
+    }
+
+    #[test]
+    fn it_break() {
+        let out = asm(
+            "int main() { int i; for (i = 0; i < 9; i++) { while (i) break; if (i) continue; } }",
+        );
+        assert!(out.contains("  je .Lend1\n  jmp .Lend1\n  jmp .Lbegin1\n.Lend1:\n"));
+        // continue runs the third clause of for
+        assert!(out.contains("  jmp .Lcontinue0\n"));
+        assert!(out.contains(".Lcontinue0:\n  mov rax, rbp\n"));
+    }
+
+    #[test]
+    fn it_switch() {
+        // sparse cases are compared one by one
+        let out = asm("int main() { int x; switch (x) { case 1: case 100: break; default: ; } }");
+        assert!(out.contains("  mov rdi, 100\n  cmp rax, rdi\n  je .Lcase0_1\n  jmp .Ldefault0\n"));
+        assert!(!out.contains(".Ltable0"));
+        // dense cases jump through a table, the gap jumps to the end without default
+        let out =
+            asm("int main() { int x; switch (x) { case 3: case 1: case 2: case 5: break; } }");
+        assert!(out.contains("  mov rdi, 1\n  sub rax, rdi\n  cmp rax, 4\n  ja .Lend0\n"));
+        assert!(out.contains(
+            ".Ltable0:\n  .long .Lcase0_1 - .Ltable0\n  .long .Lcase0_2 - .Ltable0\n  .long .Lcase0_0 - .Ltable0\n  .long .Lend0 - .Ltable0\n  .long .Lcase0_3 - .Ltable0\n"
+        ));
+    }
+
+    #[test]
+    fn it_goto() {
+        let out =
+            asm("int main() { goto begin0; begin0: return 0; } int f() { begin0: goto begin0; }");
+        assert!(out.contains("  jmp .Llabel.main.begin0\n.Llabel.main.begin0:\n"));
+        assert!(out.contains(".Llabel.f.begin0:\n  jmp .Llabel.f.begin0\n"));
+    }
+
+    #[test]
+    fn it_label() {
+        let out = asm("int main() { int i; if (1) i = 1; else i = 2; while (i) i = 0; }");
+        assert!(out.contains("  je .Lelse0\n"));
+        assert!(out.contains(".Lend0:\n.Lbegin1:\n"));
+        assert!(out.contains("  jmp .Lbegin1\n.Lend1:\n"));
+    }
 }
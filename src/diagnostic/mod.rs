@@ -0,0 +1,372 @@
+use super::parser;
+use super::sema;
+use super::tokenizer::{self, Span, Token};
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Level {
+    Error,
+    Warning,
+
+    /// additional information attached to an error or a warning
+    Note,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Error => write!(f, "error"),
+            Level::Warning => write!(f, "warning"),
+            Level::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// a message about a position in the source
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub span: Span,
+    pub notes: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    pub fn new<S>(level: Level, message: S, span: Span) -> Self
+    where
+        S: Into<String>,
+    {
+        Diagnostic {
+            level,
+            message: message.into(),
+            span,
+            notes: vec![],
+        }
+    }
+
+    pub fn error<S>(message: S, span: Span) -> Self
+    where
+        S: Into<String>,
+    {
+        Diagnostic::new(Level::Error, message, span)
+    }
+
+    pub fn warning<S>(message: S, span: Span) -> Self
+    where
+        S: Into<String>,
+    {
+        Diagnostic::new(Level::Warning, message, span)
+    }
+
+    pub fn note<S>(self, message: S, span: Span) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut notes = self.notes;
+        notes.push(Diagnostic::new(Level::Note, message, span));
+        Diagnostic { notes, ..self }
+    }
+}
+
+/// 'x' or 'y' or 'z' as a list in a message
+fn one_of(items: Vec<String>) -> String {
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, init)) => format!("{} or {}", init.join(", "), last),
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Identity(_) => "identifier".into(),
+        Token::Number(..) => "number".into(),
+        Token::Str(_) => "string literal".into(),
+        token => format!("'{}'", token),
+    }
+}
+
+impl From<tokenizer::Error> for Diagnostic {
+    fn from(e: tokenizer::Error) -> Self {
+        match e {
+            tokenizer::Error::Expected(expected, span) => Diagnostic::error(
+                format!(
+                    "expected {}",
+                    one_of(
+                        expected
+                            .iter()
+                            .map(|c| format!("{:?}", *c as char))
+                            .collect()
+                    )
+                ),
+                span,
+            ),
+            tokenizer::Error::Unexpected(c, span) if c.is_ascii() => {
+                Diagnostic::error(format!("stray {:?} in program", c as char), span)
+            }
+            tokenizer::Error::Unexpected(c, span) => {
+                Diagnostic::error(format!("stray '\\{:03o}' in program", c), span)
+            }
+            tokenizer::Error::UnterminatedComment(span) => {
+                Diagnostic::error("unterminated comment", span)
+            }
+            tokenizer::Error::Unterminated(quote, span) => Diagnostic::error(
+                format!("missing terminating {} character", quote as char),
+                span,
+            ),
+            tokenizer::Error::EmptyCharacter(span) => {
+                Diagnostic::error("empty character constant", span)
+            }
+            tokenizer::Error::UnknownEscape(c, span) => {
+                Diagnostic::error(format!("unknown escape sequence: '\\{}'", c as char), span)
+            }
+            tokenizer::Error::EscapeOutOfRange(span) => {
+                Diagnostic::error("escape sequence out of range", span)
+            }
+            tokenizer::Error::InvalidDigit(c, radix, span) => Diagnostic::error(
+                format!(
+                    "invalid digit \"{}\" in {} constant",
+                    c as char,
+                    if radix == 8 { "octal" } else { "binary" }
+                ),
+                span,
+            ),
+            tokenizer::Error::InvalidSuffix(suffix, span) => Diagnostic::error(
+                format!("invalid suffix \"{}\" on integer constant", suffix),
+                span,
+            ),
+            tokenizer::Error::TooLarge(span) => {
+                Diagnostic::error("integer constant is too large for its type", span)
+            }
+        }
+    }
+}
+
+impl From<parser::Error> for Diagnostic {
+    fn from(e: parser::Error) -> Self {
+        match e {
+            parser::Error::Expected(expected, span) if expected.is_empty() => {
+                Diagnostic::error("syntax error", span)
+            }
+            parser::Error::Expected(expected, span) => Diagnostic::error(
+                format!(
+                    "expected {}",
+                    one_of(expected.iter().map(describe).collect())
+                ),
+                span,
+            ),
+            parser::Error::Undeclared(id, span) => {
+                Diagnostic::error(format!("'{}' undeclared", id), span)
+            }
+            parser::Error::UndefinedLabel(name, span) => {
+                Diagnostic::error(format!("label '{}' used but not defined", name), span)
+            }
+            parser::Error::DuplicateLabel(name, span, previous) => {
+                Diagnostic::error(format!("duplicate label '{}'", name), span).note(
+                    format!("previous definition of '{}' is here", name),
+                    previous,
+                )
+            }
+            parser::Error::Misplaced(Token::Break, span) => {
+                Diagnostic::error("'break' statement not within a loop or switch", span)
+            }
+            parser::Error::Misplaced(Token::Continue, span) => {
+                Diagnostic::error("'continue' statement not within a loop", span)
+            }
+            parser::Error::Misplaced(token, span) => Diagnostic::error(
+                format!("'{}' label not within a switch statement", token),
+                span,
+            ),
+            parser::Error::Redeclared(id, span, previous) => {
+                Diagnostic::error(format!("redeclaration of '{}'", id), span).note(
+                    format!("previous declaration of '{}' is here", id),
+                    previous,
+                )
+            }
+            parser::Error::Redefined(id, span, previous) => {
+                Diagnostic::error(format!("redefinition of '{}'", id), span)
+                    .note(format!("previous definition of '{}' is here", id), previous)
+            }
+            parser::Error::ConflictingTypes(id, span, previous) => {
+                Diagnostic::error(format!("conflicting types for '{}'", id), span).note(
+                    format!("previous declaration of '{}' is here", id),
+                    previous,
+                )
+            }
+            parser::Error::MissingSize(id, span) => {
+                Diagnostic::error(format!("array size missing in '{}'", id), span)
+            }
+            parser::Error::FrameTooLarge(span) => {
+                Diagnostic::error("total size of local variables is too large", span)
+            }
+            parser::Error::ArrayTooLarge(span) => {
+                Diagnostic::error("size of array is too large", span)
+            }
+        }
+    }
+}
+
+impl From<sema::Error> for Diagnostic {
+    fn from(e: sema::Error) -> Self {
+        match e {
+            sema::Error::InvalidDereference(ty, span) => {
+                Diagnostic::error(format!("cannot dereference a value of type '{}'", ty), span)
+            }
+            sema::Error::InvalidOperands(l, r, span) => Diagnostic::error(
+                format!("invalid operands of types '{}' and '{}'", l, r),
+                span,
+            ),
+            sema::Error::InvalidOperand(ty, span) => {
+                Diagnostic::error(format!("invalid operand of type '{}'", ty), span)
+            }
+            sema::Error::NotLvalue(span) => Diagnostic::error("lvalue required as operand", span),
+            sema::Error::IncompatibleTypes(to, from, span) => Diagnostic::error(
+                format!("incompatible types: cannot convert '{}' to '{}'", from, to),
+                span,
+            ),
+            sema::Error::ArgumentCount(name, params, args, span, declared) => Diagnostic::error(
+                format!(
+                    "too {} arguments to function '{}': expected {}, have {}",
+                    if args < params { "few" } else { "many" },
+                    name,
+                    params,
+                    args
+                ),
+                span,
+            )
+            .note(format!("'{}' is declared here", name), declared),
+            sema::Error::NotConstant(span) => {
+                Diagnostic::error("case label does not reduce to an integer constant", span)
+            }
+            sema::Error::NotConstantInitializer(span) => {
+                Diagnostic::error("initializer element is not constant", span)
+            }
+            sema::Error::InvalidInitializer(span) => Diagnostic::error("invalid initializer", span),
+            sema::Error::DuplicateCase(value, span, previous) => {
+                Diagnostic::error(format!("duplicate case value '{}'", value), span)
+                    .note("previously used here", previous)
+            }
+            sema::Error::DuplicateDefault(span, previous) => {
+                Diagnostic::error("multiple default labels in one switch", span)
+                    .note("this is the first default label", previous)
+            }
+        }
+    }
+}
+
+impl From<sema::Warning> for Diagnostic {
+    fn from(w: sema::Warning) -> Self {
+        match w {
+            sema::Warning::ImplicitDeclaration(name, span) => {
+                Diagnostic::warning(format!("implicit declaration of function '{}'", name), span)
+            }
+            sema::Warning::ExcessElements(span) => {
+                Diagnostic::warning("excess elements in initializer", span)
+            }
+        }
+    }
+}
+
+/// the source files which the diagnostics refer to
+pub struct Diagnostics {
+    /// name and contents, indexed by Span::file
+    files: Vec<(String, String)>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics { files: vec![] }
+    }
+
+    /// returns the id of the file for the spans in it
+    pub fn add_file<S>(&mut self, name: S, src: S) -> usize
+    where
+        S: Into<String>,
+    {
+        self.files.push((name.into(), src.into()));
+        self.files.len() - 1
+    }
+
+    /// ```text
+    /// main.c:3:1: error: expected ';'
+    /// }
+    /// ^
+    /// ```
+    pub fn render(&self, d: &Diagnostic) -> String {
+        let (name, src) = &self.files[d.span.file];
+        let start = src[..d.span.offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = src[d.span.offset..]
+            .find('\n')
+            .map_or(src.len(), |i| d.span.offset + i);
+        let line = &src[start..end];
+        // keep tabs so that the caret is under the column on any tab width
+        let padding: String = line[..d.span.offset - start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let mut result = format!(
+            "{}:{}:{}: {}: {}\n{}\n{}^\n",
+            name, d.span.line, d.span.col, d.level, d.message, line, padding
+        );
+        for note in d.notes.iter() {
+            result.push_str(&self.render(note));
+        }
+        result
+    }
+
+    /// prints the diagnostic to stderr
+    pub fn report<D>(&mut self, d: D)
+    where
+        D: Into<Diagnostic>,
+    {
+        eprint!("{}", self.render(&d.into()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::parse;
+    use super::*;
+
+    fn render(src: &str) -> String {
+        let mut diagnostics = Diagnostics::new();
+        let file = diagnostics.add_file("main.c", src);
+        let (tokens, spans) = tokenizer::tokenize(src, file).unwrap();
+        let (_, errors) = parse(&tokens, &spans);
+        errors
+            .into_iter()
+            .map(|e| diagnostics.render(&e.into()))
+            .collect()
+    }
+
+    #[test]
+    fn it_render() {
+        assert_eq!(
+            render("int main() {\n\treturn 1\n}\n"),
+            "main.c:3:1: error: expected ';'\n}\n^\n"
+        );
+        assert_eq!(
+            render("int main() {\n\tint x; x = 1 +\n\t\t;\n}"),
+            "main.c:3:3: error: expected identifier, number or '('\n\t\t;\n\t\t^\n"
+        );
+        assert_eq!(
+            render("int main() { int a; long a; }"),
+            "main.c:1:26: error: redeclaration of 'a'\n\
+             int main() { int a; long a; }\n                         ^\n\
+             main.c:1:18: note: previous declaration of 'a' is here\n\
+             int main() { int a; long a; }\n                 ^\n"
+        );
+    }
+
+    #[test]
+    fn it_render_stray() {
+        let mut diagnostics = Diagnostics::new();
+        let file = diagnostics.add_file("main.c", "int \u{e9};\n");
+        let e = tokenizer::tokenize("int \u{e9};\n", file).unwrap_err();
+        assert_eq!(
+            diagnostics.render(&e.into()),
+            "main.c:1:5: error: stray '\\303' in program\nint \u{e9};\n    ^\n"
+        );
+    }
+}
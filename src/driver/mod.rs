@@ -0,0 +1,350 @@
+use super::codegen;
+use super::diagnostic::Diagnostics;
+use super::parser::{self, Node};
+use super::sema;
+use super::tokenizer;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// exit status of the driver
+pub const SUCCESS: i32 = 0;
+/// an input has errors or can not be read
+pub const COMPILE_ERROR: i32 = 1;
+/// the command line is wrong
+pub const USAGE_ERROR: i32 = 2;
+/// the assembler or the linker fails, or an output can not be written
+pub const TOOL_ERROR: i32 = 3;
+
+pub const USAGE: &str = "usage: c [-S | -c] [-o output] file...
+  -S         write the assembly of each C file to file.s
+  -c         assemble each C file into file.o
+  -o output  write the output to this path
+  -          read C source from stdin
+without -S and -c the files are linked into an executable, a.out by default.
+.s, .o and .a files are passed to the assembler or the linker as they are.";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// -S
+    Assembly,
+
+    /// -c
+    Object,
+
+    /// the default
+    Executable,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct Options {
+    pub mode: Mode,
+    pub output: Option<String>,
+    pub inputs: Vec<String>,
+}
+
+impl Options {
+    /// the arguments without the program name
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let mut mode = Mode::Executable;
+        let mut output = None;
+        let mut inputs = vec![];
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-S" => mode = Mode::Assembly,
+                "-c" if mode != Mode::Assembly => mode = Mode::Object,
+                "-c" => {}
+                "-o" => match args.next() {
+                    Some(path) => output = Some(path),
+                    None => return Err("missing filename after '-o'".into()),
+                },
+                "-" => inputs.push(arg),
+                _ if arg.starts_with('-') => {
+                    return Err(format!("unrecognized command-line option '{}'", arg))
+                }
+                _ => inputs.push(arg),
+            }
+        }
+
+        if inputs.is_empty() {
+            return Err("no input files".into());
+        }
+        if output.is_some() && mode != Mode::Executable && inputs.len() > 1 {
+            return Err("cannot specify '-o' with '-c' or '-S' with multiple files".into());
+        }
+        Ok(Options {
+            mode,
+            output,
+            inputs,
+        })
+    }
+}
+
+fn extension(input: &str) -> Option<&str> {
+    Path::new(input).extension().and_then(|e| e.to_str())
+}
+
+/// anything but assemblies and objects is compiled as C
+fn is_c_source(input: &str) -> bool {
+    !matches!(extension(input), Some("s" | "o" | "a"))
+}
+
+/// the input is given to the linker as it is
+fn is_object(input: &str) -> bool {
+    matches!(extension(input), Some("o" | "a"))
+}
+
+/// foo/bar.c -> bar.s in the current directory, - -> -.s
+fn output_name(input: &str, extension: &str) -> String {
+    let stem = Path::new(input)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(input);
+    format!("{}.{}", stem, extension)
+}
+
+/// a path in the temporary directory which is unique in this process
+fn temporary(extension: &str) -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("c-{}-{}.{}", std::process::id(), n, extension))
+}
+
+fn read(input: &str) -> io::Result<String> {
+    if input == "-" {
+        let mut src = String::new();
+        io::stdin().read_to_string(&mut src)?;
+        Ok(src)
+    } else {
+        fs::read_to_string(input)
+    }
+}
+
+/// the checked program, or None when an error is reported
+fn compile(diagnostics: &mut Diagnostics, file: usize, src: &str) -> Option<Vec<Node>> {
+    let (tokens, spans) = match tokenizer::tokenize(src, file) {
+        Ok(tokenized) => tokenized,
+        Err(e) => {
+            diagnostics.report(e);
+            return None;
+        }
+    };
+    let (nodes, errors) = parser::parse(&tokens[..], &spans);
+    if !errors.is_empty() {
+        for e in errors {
+            diagnostics.report(e);
+        }
+        return None;
+    }
+
+    let mut checker = sema::Checker::new();
+    let result = checker.program(nodes);
+    for warning in checker.warnings {
+        diagnostics.report(warning);
+    }
+    match result {
+        Ok(nodes) => Some(nodes),
+        Err(e) => {
+            diagnostics.report(e);
+            None
+        }
+    }
+}
+
+/// writes the assembly to the path, - is stdout
+fn emit(nodes: Vec<Node>, path: &Path) -> io::Result<()> {
+    if path == Path::new("-") {
+        codegen::codegen(nodes, BufWriter::new(io::stdout().lock()))
+    } else {
+        codegen::codegen(nodes, BufWriter::new(File::create(path)?))
+    }
+}
+
+/// runs the external tool and fails unless it exits successfully
+fn tool(command: &mut Command) -> io::Result<()> {
+    let status = command.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} failed with {}",
+            command.get_program().to_string_lossy(),
+            status
+        )))
+    }
+}
+
+pub struct Driver {
+    options: Options,
+    diagnostics: Diagnostics,
+
+    /// removed when the driver finishes
+    temporaries: Vec<PathBuf>,
+}
+
+impl Driver {
+    pub fn new(options: Options) -> Self {
+        Driver {
+            options,
+            diagnostics: Diagnostics::new(),
+            temporaries: vec![],
+        }
+    }
+
+    fn temporary(&mut self, extension: &str) -> PathBuf {
+        let path = temporary(extension);
+        self.temporaries.push(path.clone());
+        path
+    }
+
+    /// the output of the input in the mode which is not the executable
+    fn output(&self, input: &str, extension: &str) -> PathBuf {
+        match &self.options.output {
+            Some(output) => PathBuf::from(output),
+            None => PathBuf::from(output_name(input, extension)),
+        }
+    }
+
+    /// compiles the C source to the assembly at the path.
+    /// false when the source has errors, which are reported.
+    fn assembly(&mut self, input: &str, path: &Path) -> io::Result<bool> {
+        let src = match read(input) {
+            Ok(src) => src,
+            Err(e) => {
+                eprintln!("c: error: {}: {}", input, e);
+                return Ok(false);
+            }
+        };
+        let name = if input == "-" { "<stdin>" } else { input };
+        let file = self.diagnostics.add_file(name, src.as_str());
+        match compile(&mut self.diagnostics, file, &src) {
+            Some(nodes) => {
+                emit(nodes, path)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// compiles and assembles the input into the object at the path
+    fn object(&mut self, input: &str, path: &Path) -> io::Result<bool> {
+        let assembly = if is_c_source(input) {
+            let assembly = self.temporary("s");
+            if !self.assembly(input, &assembly)? {
+                return Ok(false);
+            }
+            assembly
+        } else {
+            PathBuf::from(input)
+        };
+        tool(Command::new("as").arg("-o").arg(path).arg(assembly))?;
+        Ok(true)
+    }
+
+    fn build(&mut self) -> io::Result<bool> {
+        let inputs = self.options.inputs.clone();
+        let mut succeeded = true;
+        match self.options.mode {
+            Mode::Assembly => {
+                for input in inputs.iter().filter(|input| is_c_source(input)) {
+                    let path = self.output(input, "s");
+                    succeeded &= self.assembly(input, &path)?;
+                }
+            }
+            Mode::Object => {
+                for input in inputs.iter().filter(|input| !is_object(input)) {
+                    let path = self.output(input, "o");
+                    succeeded &= self.object(input, &path)?;
+                }
+            }
+            Mode::Executable => {
+                let mut objects = vec![];
+                for input in inputs.iter() {
+                    if is_object(input) {
+                        objects.push(PathBuf::from(input));
+                    } else {
+                        let path = self.temporary("o");
+                        succeeded &= self.object(input, &path)?;
+                        objects.push(path);
+                    }
+                }
+                if succeeded {
+                    let output = self.options.output.as_deref().unwrap_or("a.out");
+                    tool(Command::new("cc").arg("-o").arg(output).args(objects))?;
+                }
+            }
+        }
+        Ok(succeeded)
+    }
+
+    /// the exit status
+    pub fn run(&mut self) -> i32 {
+        let result = self.build();
+        for path in self.temporaries.iter() {
+            let _ = fs::remove_file(path);
+        }
+        match result {
+            Ok(true) => SUCCESS,
+            Ok(false) => COMPILE_ERROR,
+            Err(e) => {
+                eprintln!("c: error: {}", e);
+                TOOL_ERROR
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        Options::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn it_options() {
+        assert_eq!(
+            parse("a.c b.c ext.o"),
+            Ok(Options {
+                mode: Mode::Executable,
+                output: None,
+                inputs: vec!["a.c".into(), "b.c".into(), "ext.o".into()],
+            })
+        );
+        assert_eq!(
+            parse("-S -o - -"),
+            Ok(Options {
+                mode: Mode::Assembly,
+                output: Some("-".into()),
+                inputs: vec!["-".into()],
+            })
+        );
+        assert_eq!(parse("-c -S a.c").map(|o| o.mode), Ok(Mode::Assembly));
+        assert_eq!(parse("-S -c a.c").map(|o| o.mode), Ok(Mode::Assembly));
+        assert!(parse("").is_err());
+        assert!(parse("a.c -o").is_err());
+        assert!(parse("-x a.c").is_err());
+        assert!(parse("-c -o a.o a.c b.c").is_err());
+        assert!(parse("-o app a.c b.c").is_ok());
+    }
+
+    #[test]
+    fn it_output_name() {
+        assert_eq!(output_name("src/main.c", "s"), "main.s");
+        assert_eq!(output_name("main", "o"), "main.o");
+        assert_eq!(output_name("-", "s"), "-.s");
+        assert!(!is_c_source("ext.o"));
+        assert!(!is_c_source("start.s"));
+        assert!(is_c_source("main.c"));
+        assert!(is_c_source("-"));
+        assert!(is_object("libext.a"));
+        assert!(!is_object("start.s"));
+    }
+}
@@ -1,17 +1,19 @@
 mod codegen;
+mod diagnostic;
+mod driver;
 mod parser;
+mod sema;
 mod tokenizer;
 
-use std::fs;
-use std::io::Result;
+use std::process;
 
-fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    if let Some(path) = args.get(1) {
-        let src = fs::read_to_string(path)?;
-        let tokenize = tokenizer::tokenize(&src);
-        let node = parser::parse(&tokenize[..]).unwrap();
-        codegen::codegen(node);
-    }
-    Ok(())
+fn main() {
+    let options = match driver::Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("c: error: {}\n{}", e, driver::USAGE);
+            process::exit(driver::USAGE_ERROR);
+        }
+    };
+    process::exit(driver::Driver::new(options).run());
 }
@@ -1,11 +1,16 @@
 mod node;
+#[allow(clippy::module_inception)]
 mod parser;
-use super::tokenizer::Token;
+mod ty;
+use super::tokenizer::{Span, Token};
 
-pub use node::Node;
-pub use parser::{Error, Result};
+pub use node::{Node, NodeKind};
+pub use parser::Error;
+pub use ty::Type;
 
-pub fn parse(src: &[Token]) -> Result<Vec<Node>> {
-    let mut p = parser::Parser::new();
-    p.program(src).map(|(nodes, _)| nodes)
+/// the nodes lack the parts which have the errors
+pub fn parse(src: &[Token], spans: &[Span]) -> (Vec<Node>, Vec<Error>) {
+    let mut p = parser::Parser::new(spans);
+    let nodes = p.program(src);
+    (nodes, p.errors)
 }
@@ -1,24 +1,95 @@
-/// program    = stmt*
-/// stmt       = expr ";"
+use super::super::tokenizer::Span;
+use super::Type;
+
+/// program    = (function | global)*
+/// function   = type ident "(" (params | "void")? ")" ("{" stmt* "}" | ";")
+/// global     = type ident ("[" num? "]")? ("[" num "]")* ("=" init)? ";"
+/// init       = assign | "{" init ("," init)* ","? "}"
+/// params     = param ("," param)*
+/// param      = type ident ("[" num? "]")? ("[" num "]")*
+/// type       = ("char" | "short" "int"? | "int" | "long" "long"? "int"?) "*"*
+/// stmt       = expr? ";"
+///                 | type ident ("[" num "]")* ("=" assign)? ";"
 ///                 | "{" stmt* "}"
 ///                 | "return" expr ";"
 ///                 | "if" "(" expr ")" stmt ("else" stmt)?
 ///                 | "while" "(" expr ")" stmt
 ///                 | "for" "(" expr? ";" expr? ";" expr? ")" stmt
-/// expr       = assign
-/// assign     = equality ("=" assign)?
+///                 | "do" stmt "while" "(" expr ")" ";"
+///                 | "switch" "(" expr ")" stmt
+///                 | "case" cond ":" stmt
+///                 | "default" ":" stmt
+///                 | ident ":" stmt
+///                 | "goto" ident ";"
+///                 | "break" ";"
+///                 | "continue" ";"
+/// expr       = assign ("," assign)*
+/// assign     = cond (("=" | "+=" | "-=" | "*=" | "/=" | "%="
+///                 | "&=" | "|=" | "^=" | "<<=" | ">>=") assign)?
+/// cond       = logor ("?" expr ":" cond)?
+/// logor      = logand ("||" logand)*
+/// logand     = bitor ("&&" bitor)*
+/// bitor      = bitxor ("|" bitxor)*
+/// bitxor     = bitand ("^" bitand)*
+/// bitand     = equality ("&" equality)*
 /// equality   = relational ("==" relational | "!=" relational)*
-/// relational = add ("<" add | "<=" add | ">" add | ">=" add)*
+/// relational = shift ("<" shift | "<=" shift | ">" shift | ">=" shift)*
+/// shift      = add ("<<" add | ">>" add)*
 /// add        = mul ("+" mul | "-" mul)*
-/// mul        = unary ("*" unary | "/" unary)*
-/// unary      = ("+" | "-")? primary
-/// primary    = num | ident | "(" expr ")"
+/// mul        = unary ("*" unary | "/" unary | "%" unary)*
+/// unary      = ("+" | "-" | "~" | "!" | "&" | "*" | "++" | "--") unary
+///                 | "(" type ")" unary
+///                 | postfix
+/// postfix    = primary ("[" expr "]" | "++" | "--")*
+/// primary    = num | str+ | ident ("(" args? ")")? | "(" expr ")"
+/// args       = assign ("," assign)*
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+
+    /// type of the value which the node evaluates to.
+    /// the parser leaves it None and the semantic analysis fills it for every expression.
+    pub ty: Option<Type>,
+
+    /// where the node is written in the source
+    pub span: Span,
+}
+
+/// the span is not compared,
+/// the same tree is equal wherever it is written
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.ty == other.ty
+    }
+}
+impl Eq for Node {}
+
 #[derive(PartialEq, Eq, Debug, Clone)]
-pub enum Node {
+pub enum NodeKind {
     Number(isize),
 
-    /// id, offset from RBP
-    LocalVariable(String, usize),
+    /// the bytes of a string literal, adjacent literals are concatenated.
+    /// it is an array of char with the terminating null character
+    Str(Vec<u8>),
+
+    /// id, offset from RBP, type
+    LocalVariable(String, usize, Type),
+
+    /// a variable at file scope, which is accessed by its symbol
+    GlobalVariable(String, Type),
+
+    /// {node, node, node}
+    /// the initializer of an array, the elements after the last are filled with 0
+    InitializerList(Vec<Node>),
+
+    /// &node
+    Address(Box<Node>),
+
+    /// *node
+    Dereference(Box<Node>),
+
+    /// converts the value of the node to the type of this node
+    Cast(Box<Node>),
 
     /// left == right
     Equal(Box<Node>, Box<Node>),
@@ -44,8 +115,73 @@ pub enum Node {
     /// left / right
     Devide(Box<Node>, Box<Node>),
 
+    /// left % right
+    Modulo(Box<Node>, Box<Node>),
+
+    /// left & right
+    BitAnd(Box<Node>, Box<Node>),
+
+    /// left | right
+    BitOr(Box<Node>, Box<Node>),
+
+    /// left ^ right
+    BitXor(Box<Node>, Box<Node>),
+
+    /// left << right
+    ShiftLeft(Box<Node>, Box<Node>),
+
+    /// left >> right
+    ShiftRight(Box<Node>, Box<Node>),
+
+    /// left && right
+    /// the right is not evaluated when the left is 0
+    And(Box<Node>, Box<Node>),
+
+    /// left || right
+    /// the right is not evaluated when the left is not 0
+    Or(Box<Node>, Box<Node>),
+
+    /// !node
+    Not(Box<Node>),
+
+    /// condition ? then : else
+    /// only one of then and else is evaluated
+    Conditional(Box<Node>, Box<Node>, Box<Node>),
+
+    /// left, right
+    /// the value of the left is discarded
+    Comma(Box<Node>, Box<Node>),
+
+    /// +node
+    /// the value after the integer promotion, which is not an lvalue
+    Positive(Box<Node>),
+
+    /// -node
+    Negate(Box<Node>),
+
+    /// ~node
+    BitNot(Box<Node>),
+
     Assign(Box<Node>, Box<Node>),
 
+    /// ```
+    /// a += 2
+    /// ```
+    /// CompoundAssign(a, Plus(Stored, 2))
+    /// the address of the target is evaluated only once,
+    /// Stored in the operation is the value of the target.
+    CompoundAssign(Box<Node>, Box<Node>),
+
+    /// ```
+    /// a++
+    /// ```
+    /// PostAssign(a, Plus(Stored, 1))
+    /// CompoundAssign which evaluates to the value before the assignment
+    PostAssign(Box<Node>, Box<Node>),
+
+    /// the value of the target of the innermost CompoundAssign or PostAssign
+    Stored,
+
     Return(Box<Node>),
 
     /// ```
@@ -76,6 +212,47 @@ pub enum Node {
     /// While(true, print 1)
     While(Box<Node>, Box<Node>),
 
+    /// ```
+    /// do
+    ///     print 1
+    /// while (true)
+    /// ```
+    /// DoWhile(print 1, true)
+    DoWhile(Box<Node>, Box<Node>),
+
+    /// ```
+    /// switch (x) {
+    /// case 1:
+    ///     print 1
+    /// default:
+    ///     print 2
+    /// }
+    /// ```
+    /// Switch(x, Block(...), vec![1], true)
+    /// the values of the cases in the body and whether it has default
+    /// are filled by the semantic analysis.
+    Switch(Box<Node>, Box<Node>, Vec<isize>, bool),
+
+    /// the statement after `case value:`.
+    /// the value is a Number after the semantic analysis.
+    Case(Box<Node>, Box<Node>),
+
+    /// the statement after `default:`
+    Default(Box<Node>),
+
+    /// the statement after `name:`
+    Label(String, Box<Node>),
+
+    /// jumps to the label in the same function
+    Goto(String),
+
+    /// jumps out of the innermost loop or switch
+    Break,
+
+    /// jumps to the next iteration of the innermost loop,
+    /// which starts from the third clause in a for loop
+    Continue,
+
     /// ```
     /// {
     /// node;
@@ -83,56 +260,228 @@ pub enum Node {
     /// node;
     /// }
     /// Block(vec![node, node, node])
-    Block(Box<Vec<Node>>),
+    Block(Vec<Node>),
+
+    /// ```
+    /// int add(int a, int b) {
+    ///     return a + b;
+    /// }
+    /// ```
+    /// Function("add", int, vec![a, b], true, Some(Block(...)), 16)
+    /// the bool is false when the parameters are unspecified as in int f(),
+    /// and the calls of the function are not checked.
+    /// the body is None when the function is only declared.
+    /// the last is the size of the stack frame for the local variables.
+    Function(String, Type, Vec<Node>, bool, Option<Box<Node>>, usize),
+
+    /// ```
+    /// add(1, 2)
+    /// ```
+    /// Call("add", vec![1, 2])
+    Call(String, Vec<Node>),
+
+    /// ```
+    /// long counter = 1;
+    /// ```
+    /// GlobalDefinition("counter", long, Some(1))
+    /// the variable without the initializer is filled with 0.
+    /// the semantic analysis replaces every value in the initializer with a constant:
+    /// a Number, or the Address of a variable or a string literal plus the offset in bytes.
+    GlobalDefinition(String, Type, Option<Box<Node>>),
 }
 
 impl Node {
+    pub fn new(kind: NodeKind) -> Self {
+        Node {
+            kind,
+            ty: None,
+            span: Span::default(),
+        }
+    }
+
+    pub fn at(self, span: Span) -> Self {
+        Node { span, ..self }
+    }
+
+    /// type of the expression
+    /// only available after the semantic analysis
+    pub fn ty(&self) -> &Type {
+        self.ty.as_ref().expect("the node is not typed")
+    }
+
     pub fn number(n: isize) -> Self {
-        Node::Number(n)
+        Node::new(NodeKind::Number(n))
+    }
+    /// a constant whose type is decided by how it is written.
+    /// the semantic analysis decides the type of the others by the value
+    pub fn typed_number(n: isize, ty: Type) -> Self {
+        Node {
+            ty: Some(ty),
+            ..Node::number(n)
+        }
+    }
+    pub fn string(s: Vec<u8>) -> Self {
+        Node::new(NodeKind::Str(s))
+    }
+    pub fn local_variable<S>(n: S, offset: usize, ty: Type) -> Self
+    where
+        S: Into<String>,
+    {
+        Node::new(NodeKind::LocalVariable(n.into(), offset, ty))
     }
-    pub fn local_variable<S>(n: S, offset: usize) -> Self
+    pub fn global_variable<S>(n: S, ty: Type) -> Self
     where
         S: Into<String>,
     {
-        Node::LocalVariable(n.into(), offset)
+        Node::new(NodeKind::GlobalVariable(n.into(), ty))
+    }
+    pub fn initializer_list(nodes: Vec<Self>) -> Self {
+        Node::new(NodeKind::InitializerList(nodes))
+    }
+    pub fn address(node: Self) -> Self {
+        Node::new(NodeKind::Address(Box::new(node)))
+    }
+    pub fn dereference(node: Self) -> Self {
+        Node::new(NodeKind::Dereference(Box::new(node)))
+    }
+    /// the cast is placed where the node is
+    pub fn cast(node: Self, ty: Type) -> Self {
+        Node {
+            span: node.span,
+            kind: NodeKind::Cast(Box::new(node)),
+            ty: Some(ty),
+        }
     }
     pub fn equal(left: Self, right: Self) -> Self {
-        Node::Equal(Box::new(left), Box::new(right))
+        Node::new(NodeKind::Equal(Box::new(left), Box::new(right)))
     }
     pub fn unequal(left: Self, right: Self) -> Self {
-        Node::UnEqual(Box::new(left), Box::new(right))
+        Node::new(NodeKind::UnEqual(Box::new(left), Box::new(right)))
     }
     pub fn less(left: Self, right: Self) -> Self {
-        Node::Less(Box::new(left), Box::new(right))
+        Node::new(NodeKind::Less(Box::new(left), Box::new(right)))
     }
     pub fn less_equal(left: Self, right: Self) -> Self {
-        Node::LessEqual(Box::new(left), Box::new(right))
+        Node::new(NodeKind::LessEqual(Box::new(left), Box::new(right)))
     }
     pub fn plus(left: Self, right: Self) -> Self {
-        Node::Plus(Box::new(left), Box::new(right))
+        Node::new(NodeKind::Plus(Box::new(left), Box::new(right)))
     }
     pub fn minus(left: Self, right: Self) -> Self {
-        Node::Minus(Box::new(left), Box::new(right))
+        Node::new(NodeKind::Minus(Box::new(left), Box::new(right)))
     }
     pub fn multiple(left: Self, right: Self) -> Self {
-        Node::Multiple(Box::new(left), Box::new(right))
+        Node::new(NodeKind::Multiple(Box::new(left), Box::new(right)))
     }
     pub fn devide(left: Self, right: Self) -> Self {
-        Node::Devide(Box::new(left), Box::new(right))
+        Node::new(NodeKind::Devide(Box::new(left), Box::new(right)))
+    }
+    pub fn modulo(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::Modulo(Box::new(left), Box::new(right)))
+    }
+    pub fn bit_and(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::BitAnd(Box::new(left), Box::new(right)))
+    }
+    pub fn bit_or(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::BitOr(Box::new(left), Box::new(right)))
+    }
+    pub fn bit_xor(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::BitXor(Box::new(left), Box::new(right)))
+    }
+    pub fn shift_left(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::ShiftLeft(Box::new(left), Box::new(right)))
+    }
+    pub fn shift_right(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::ShiftRight(Box::new(left), Box::new(right)))
+    }
+    pub fn and(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::And(Box::new(left), Box::new(right)))
+    }
+    pub fn or(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::Or(Box::new(left), Box::new(right)))
+    }
+    pub fn not(node: Self) -> Self {
+        Node::new(NodeKind::Not(Box::new(node)))
+    }
+    pub fn conditional(condition: Self, then: Self, else_n: Self) -> Self {
+        Node::new(NodeKind::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(else_n),
+        ))
+    }
+    pub fn comma(left: Self, right: Self) -> Self {
+        Node::new(NodeKind::Comma(Box::new(left), Box::new(right)))
+    }
+    pub fn positive(node: Self) -> Self {
+        Node::new(NodeKind::Positive(Box::new(node)))
+    }
+    pub fn negate(node: Self) -> Self {
+        Node::new(NodeKind::Negate(Box::new(node)))
+    }
+    pub fn bit_not(node: Self) -> Self {
+        Node::new(NodeKind::BitNot(Box::new(node)))
     }
     pub fn assign(left: Self, right: Self) -> Self {
-        Node::Assign(Box::new(left), Box::new(right))
+        Node::new(NodeKind::Assign(Box::new(left), Box::new(right)))
+    }
+    pub fn compound_assign(target: Self, operation: Self) -> Self {
+        Node::new(NodeKind::CompoundAssign(
+            Box::new(target),
+            Box::new(operation),
+        ))
+    }
+    pub fn post_assign(target: Self, operation: Self) -> Self {
+        Node::new(NodeKind::PostAssign(Box::new(target), Box::new(operation)))
+    }
+    pub fn stored() -> Self {
+        Node::new(NodeKind::Stored)
+    }
+    pub fn do_while(body: Self, condition: Self) -> Self {
+        Node::new(NodeKind::DoWhile(Box::new(body), Box::new(condition)))
+    }
+    pub fn switch(condition: Self, body: Self, cases: Vec<isize>, default: bool) -> Self {
+        Node::new(NodeKind::Switch(
+            Box::new(condition),
+            Box::new(body),
+            cases,
+            default,
+        ))
+    }
+    pub fn case(value: Self, node: Self) -> Self {
+        Node::new(NodeKind::Case(Box::new(value), Box::new(node)))
+    }
+    pub fn default(node: Self) -> Self {
+        Node::new(NodeKind::Default(Box::new(node)))
+    }
+    pub fn label<S>(name: S, node: Self) -> Self
+    where
+        S: Into<String>,
+    {
+        Node::new(NodeKind::Label(name.into(), Box::new(node)))
+    }
+    pub fn goto<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Node::new(NodeKind::Goto(name.into()))
+    }
+    pub fn break_n() -> Self {
+        Node::new(NodeKind::Break)
+    }
+    pub fn continue_n() -> Self {
+        Node::new(NodeKind::Continue)
     }
     pub fn return_n(node: Self) -> Self {
-        Node::Return(Box::new(node))
+        Node::new(NodeKind::Return(Box::new(node)))
     }
 
     pub fn if_n(condition: Self, true_action: Self, false_action: Option<Self>) -> Self {
-        Node::If(
+        Node::new(NodeKind::If(
             Box::new(condition),
             Box::new(true_action),
-            false_action.map(|fa| Box::new(fa)),
-        )
+            false_action.map(Box::new),
+        ))
     }
 
     pub fn for_n(
@@ -141,19 +490,58 @@ impl Node {
         condition3: Option<Self>,
         node: Self,
     ) -> Self {
-        Node::For(
-            condition1.map(|c| Box::new(c)),
-            condition2.map(|c| Box::new(c)),
-            condition3.map(|c| Box::new(c)),
+        Node::new(NodeKind::For(
+            condition1.map(Box::new),
+            condition2.map(Box::new),
+            condition3.map(Box::new),
             Box::new(node),
-        )
+        ))
     }
 
     pub fn while_n(condition: Self, node: Self) -> Self {
-        Node::While(Box::new(condition), Box::new(node))
+        Node::new(NodeKind::While(Box::new(condition), Box::new(node)))
     }
 
     pub fn block(nodes: Vec<Self>) -> Self {
-        Node::Block(Box::new(nodes))
+        Node::new(NodeKind::Block(nodes))
+    }
+
+    pub fn function<S>(
+        name: S,
+        ty: Type,
+        params: Vec<Self>,
+        prototyped: bool,
+        body: Option<Self>,
+        frame_size: usize,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Node::new(NodeKind::Function(
+            name.into(),
+            ty,
+            params,
+            prototyped,
+            body.map(Box::new),
+            frame_size,
+        ))
+    }
+
+    pub fn global_definition<S>(name: S, ty: Type, init: Option<Self>) -> Self
+    where
+        S: Into<String>,
+    {
+        Node::new(NodeKind::GlobalDefinition(
+            name.into(),
+            ty,
+            init.map(Box::new),
+        ))
+    }
+
+    pub fn call<S>(name: S, args: Vec<Self>) -> Self
+    where
+        S: Into<String>,
+    {
+        Node::new(NodeKind::Call(name.into(), args))
     }
 }
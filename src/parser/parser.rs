@@ -1,49 +1,427 @@
-use super::super::tokenizer::Token;
-use super::Node;
+use super::super::tokenizer::{Literal, Span, Token};
+use super::{Node, NodeKind, Type};
 use std::collections::HashMap;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
-    Expected(Vec<Token>),
+    /// one of the tokens is expected at the position
+    Expected(Vec<Token>, Span),
+
+    /// the identity is used without a declaration
+    Undeclared(String, Span),
+
+    /// the identity is declared twice in the same scope.
+    /// the last is where the previous declaration is.
+    Redeclared(String, Span, Span),
+
+    /// the function has two bodies.
+    /// the last is where the previous definition is.
+    Redefined(String, Span, Span),
+
+    /// the declarations of the function disagree on the types.
+    /// the last is where the previous declaration is.
+    ConflictingTypes(String, Span, Span),
+
+    /// goto jumps to the label which is not in the function
+    UndefinedLabel(String, Span),
+
+    /// the label is defined twice in the function.
+    /// the last is where the previous definition is.
+    DuplicateLabel(String, Span, Span),
+
+    /// break or continue is not in a statement which it can jump out of,
+    /// or case or default is not in a switch
+    Misplaced(Token, Span),
+
+    /// the array is declared without the outermost dimension or an initializer
+    /// which decides it
+    MissingSize(String, Span),
+
+    /// the array occupies more bytes than an object can,
+    /// which is where the dimension is
+    ArrayTooLarge(Span),
+
+    /// the local variables of the function do not fit in the stack frame,
+    /// which is where the first variable out of the frame is declared
+    FrameTooLarge(Span),
 }
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    pub fn span(&self) -> Span {
+        match self {
+            Error::Expected(_, span)
+            | Error::Undeclared(_, span)
+            | Error::Redeclared(_, span, _)
+            | Error::Redefined(_, span, _)
+            | Error::ConflictingTypes(_, span, _)
+            | Error::UndefinedLabel(_, span)
+            | Error::DuplicateLabel(_, span, _)
+            | Error::Misplaced(_, span)
+            | Error::MissingSize(_, span)
+            | Error::ArrayTooLarge(span)
+            | Error::FrameTooLarge(span) => *span,
+        }
+    }
+}
+
+/// what an identity at file scope names
+enum External {
+    Variable(Type),
+
+    /// return type, parameter types which are None when they are unspecified,
+    /// and whether the body is defined
+    Function(Type, Option<Vec<Type>>, bool),
+}
+
 pub struct Parser {
-    id_jar: HashMap<String, usize>,
+    /// variables visible from the current position.
+    /// every block has its own scope and the innermost scope is the last.
+    scopes: Vec<HashMap<String, (usize, Type, Span)>>,
+
+    /// variables at file scope declared so far, which are behind every block
+    globals: HashMap<String, (External, Span)>,
+
+    /// bytes used by the local variables alive at the current position
+    stack_size: usize,
+
+    /// the peak of stack_size in the current function
+    frame_size: usize,
+
+    /// the first variable which does not fit in the stack frame of the current function
+    oversized: Option<Span>,
+
+    /// labels defined so far in the current function and where they are
+    labels: HashMap<String, Span>,
+
+    /// the labels which goto jumps to in the current function.
+    /// they are resolved at the end of the function.
+    gotos: Vec<(String, Span)>,
+
+    /// statements enclosing the current position which break jumps out of.
+    /// the innermost is the last.
+    breakables: Vec<Breakable>,
+
+    /// positions of the tokens and the end of the input
+    spans: Vec<Span>,
+
+    /// errors from which the parser has recovered
+    pub errors: Vec<Error>,
+}
+
+/// the operation of the compound assignment operator
+fn compound_operator(token: &Token) -> Option<fn(Node, Node) -> Node> {
+    match token {
+        Token::PlusAssign => Some(Node::plus),
+        Token::MinusAssign => Some(Node::minus),
+        Token::MultipleAssign => Some(Node::multiple),
+        Token::DevideAssign => Some(Node::devide),
+        Token::PercentAssign => Some(Node::modulo),
+        Token::AmpersandAssign => Some(Node::bit_and),
+        Token::PipeAssign => Some(Node::bit_or),
+        Token::CaretAssign => Some(Node::bit_xor),
+        Token::ShiftLeftAssign => Some(Node::shift_left),
+        Token::ShiftRightAssign => Some(Node::shift_right),
+        _ => None,
+    }
+}
+
+/// the first type in the list for the form of the constant which can represent the value
+fn literal_type(n: isize, literal: &Literal) -> Type {
+    let candidates = match (literal.decimal, literal.unsigned, literal.long) {
+        (true, false, false) => vec![Type::Int, Type::Long],
+        (false, false, false) => vec![Type::Int, Type::UnsignedInt, Type::Long, Type::UnsignedLong],
+        (_, true, false) => vec![Type::UnsignedInt, Type::UnsignedLong],
+        (true, false, true) => vec![Type::Long],
+        (false, false, true) => vec![Type::Long, Type::UnsignedLong],
+        (_, true, true) => vec![Type::UnsignedLong],
+    };
+    candidates
+        .into_iter()
+        .find(|ty| n as u64 <= ty.max())
+        .expect("the tokenizer rejects a constant which no type can represent")
+}
+
+/// a statement which break jumps out of
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Breakable {
+    /// continue also jumps in a loop
+    Loop,
+
+    /// case and default are in a switch
+    Switch,
+}
+
+/// how many initializers from the head of the list an object of the type takes
+/// when the braces around it are omitted
+fn consumed(items: &[Node], ty: &Type) -> usize {
+    match ty {
+        Type::Array(of, n) => {
+            let mut i = 0;
+            for _ in 0..*n {
+                if i >= items.len() {
+                    break;
+                }
+                i += element_consumed(&items[i..], of);
+            }
+            i
+        }
+        _ => 1,
+    }
+}
+
+/// a braced initializer, or a string literal for an array of char,
+/// initializes the element by itself
+fn element_consumed(items: &[Node], ty: &Type) -> usize {
+    match (&items[0].kind, ty) {
+        (NodeKind::InitializerList(_), _) => 1,
+        (NodeKind::Str(_), Type::Array(of, _)) if **of == Type::Char => 1,
+        // an element of no size takes nothing
+        _ => consumed(items, ty).max(1),
+    }
+}
+
+/// the outermost dimension of the array which is omitted in the declaration
+fn length(init: &Node, of: &Type) -> usize {
+    match &init.kind {
+        NodeKind::InitializerList(items) => {
+            let (mut i, mut n) = (0, 0);
+            while i < items.len() {
+                i += element_consumed(&items[i..], of);
+                n += 1;
+            }
+            n
+        }
+        // the terminating null character
+        NodeKind::Str(s) => s.len() + 1,
+        _ => 1,
+    }
+}
+
+/// the prologue reserves the frame with sub rsp, which takes a 32 bit immediate.
+/// the limit is a multiple of 16 so that the aligned frame fits in it too
+const MAX_FRAME_SIZE: usize = i32::MAX as usize & !15;
+
+/// unspecified parameters take the arguments after the integer promotion,
+/// so they agree with a list of types which the promotion does not change
+fn compatible_params(l: &Option<Vec<Type>>, r: &Option<Vec<Type>>) -> bool {
+    match (l, r) {
+        (Some(l), Some(r)) => l == r,
+        (Some(params), None) | (None, Some(params)) => params.iter().all(|ty| ty.promoted() == *ty),
+        (None, None) => true,
+    }
+}
+
+fn align_to(n: usize, align: usize) -> usize {
+    n.div_ceil(align) * align
 }
 
 impl Parser {
-    pub fn new() -> Self {
+    pub fn new(spans: &[Span]) -> Self {
         Self {
-            id_jar: HashMap::new(),
+            scopes: vec![],
+            globals: HashMap::new(),
+            stack_size: 0,
+            frame_size: 0,
+            oversized: None,
+            labels: HashMap::new(),
+            gotos: vec![],
+            breakables: vec![],
+            spans: spans.to_vec(),
+            errors: vec![],
+        }
+    }
+
+    /// an error reported again by the enclosing constructs is recorded once
+    fn report(&mut self, e: Error) {
+        if self.errors.last() != Some(&e) {
+            self.errors.push(e);
+        }
+    }
+
+    /// panic-mode recovery.
+    /// skips the tokens from the position of the error
+    /// to the end of the statement, which is the next ";" or a "}" closing a block.
+    /// a "}" which closes the enclosing block is left for the block.
+    fn synchronize<'a>(&self, tokens: &'a [Token], e: &Error) -> &'a [Token] {
+        let start = self.spans.len() - 1 - tokens.len();
+        let position = self
+            .spans
+            .binary_search_by_key(&e.span().offset, |span| span.offset)
+            .unwrap_or(start);
+        let mut tokens = &tokens[position.max(start) - start..];
+        let mut depth = 0;
+        loop {
+            match tokens {
+                [Token::EndExpr, rest @ ..] if depth == 0 => return rest,
+                [Token::RightBlock, ..] if depth == 0 => return tokens,
+                [Token::RightBlock, rest @ ..] if depth == 1 => return rest,
+                [Token::RightBlock, rest @ ..] => {
+                    depth -= 1;
+                    tokens = rest;
+                }
+                [Token::LeftBlock, rest @ ..] => {
+                    depth += 1;
+                    tokens = rest;
+                }
+                [_, rest @ ..] => tokens = rest,
+                [] => return tokens,
+            }
+        }
+    }
+
+    /// position of the head of the tokens.
+    /// the tokens are always the rest of the whole input,
+    /// so the head is found by counting from the end.
+    fn span(&self, tokens: &[Token]) -> Span {
+        self.spans[self.spans.len() - 1 - tokens.len()]
+    }
+
+    /// the variable occupies [rbp-offset, rbp-offset+size)
+    fn make_offset(&mut self, ty: &Type, span: Span) -> usize {
+        let stack_size = align_to(self.stack_size + ty.size(), ty.align());
+        if stack_size > MAX_FRAME_SIZE {
+            // the variable is left out of the frame since the function is rejected
+            self.oversized.get_or_insert(span);
+            return self.stack_size;
+        }
+        self.stack_size = stack_size;
+        self.frame_size = self.frame_size.max(self.stack_size);
+        self.stack_size
+    }
+
+    /// returns the stack size to restore when the scope is left
+    fn enter_scope(&mut self) -> usize {
+        self.scopes.push(HashMap::new());
+        self.stack_size
+    }
+
+    fn leave_scope(&mut self, stack_size: usize) {
+        self.scopes.pop();
+        // the variables of the scope are dead here,
+        // so the following sibling scopes reuse their slots
+        self.stack_size = stack_size;
+    }
+
+    fn declare(&mut self, key: &str, ty: Type, span: Span) -> Result<Node> {
+        if let Some(scope) = self.scopes.last() {
+            if let Some((_, _, previous)) = scope.get(key) {
+                return Err(Error::Redeclared(key.into(), span, *previous));
+            }
+        }
+        let offset = self.make_offset(&ty, span);
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("declaration out of any scope");
+        let _ = scope.insert(key.into(), (offset, ty.clone(), span));
+        Ok(Node::local_variable(key, offset, ty).at(span))
+    }
+
+    /// functions and variables share the names at file scope
+    fn declare_global(&mut self, key: &str, ty: Type, span: Span) -> Result<()> {
+        if let Some((_, previous)) = self.globals.get(key) {
+            return Err(Error::Redeclared(key.into(), span, *previous));
         }
+        let _ = self
+            .globals
+            .insert(key.into(), (External::Variable(ty), span));
+        Ok(())
     }
 
-    fn make_offset(&mut self, key: &str) -> usize {
-        if let Some(offset) = self.id_jar.get(key) {
-            *offset
-        } else {
-            let offset = self.id_jar.len() * 8;
-            let _ = self.id_jar.insert(key.into(), offset);
-            offset
+    /// a function may be declared any number of times with compatible types,
+    /// and defined once
+    fn declare_function(
+        &mut self,
+        key: &str,
+        ty: Type,
+        params: Option<Vec<Type>>,
+        defined: bool,
+        span: Span,
+    ) -> Result<()> {
+        match self.globals.get(key) {
+            Some((External::Variable(_), previous)) => {
+                Err(Error::Redeclared(key.into(), span, *previous))
+            }
+            Some((External::Function(_, _, true), previous)) if defined => {
+                Err(Error::Redefined(key.into(), span, *previous))
+            }
+            Some((External::Function(r, p, _), previous))
+                if *r != ty || !compatible_params(p, &params) =>
+            {
+                Err(Error::ConflictingTypes(key.into(), span, *previous))
+            }
+            // the parameters once specified are kept, and so is where the definition is
+            Some((External::Function(_, p, previous_defined), previous)) => {
+                let params = params.or_else(|| p.clone());
+                let (defined, span) = match previous_defined {
+                    true => (true, *previous),
+                    false => (defined, span),
+                };
+                let function = External::Function(ty, params, defined);
+                let _ = self.globals.insert(key.into(), (function, span));
+                Ok(())
+            }
+            None => {
+                let function = External::Function(ty, params, defined);
+                let _ = self.globals.insert(key.into(), (function, span));
+                Ok(())
+            }
         }
     }
 
+    /// the innermost declaration of the identity
+    fn variable(&mut self, key: &str, span: Span) -> Result<Node> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(key))
+            .map(|(offset, ty, _)| Node::local_variable(key, *offset, ty.clone()).at(span))
+            .or_else(|| match self.globals.get(key) {
+                Some((External::Variable(ty), _)) => {
+                    Some(Node::global_variable(key, ty.clone()).at(span))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| Error::Undeclared(key.into(), span))
+    }
+
     fn identity<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
         match tokens {
             [Token::Identity(head), tail @ ..] => {
-                let offset = self.make_offset(head);
-                Ok((Node::local_variable(head, offset), tail))
+                Ok((self.variable(head, self.span(tokens))?, tail))
             }
-            _ => Err(Error::Expected(vec![Token::identity("")])),
+            _ => Err(Error::Expected(
+                vec![Token::identity("")],
+                self.span(tokens),
+            )),
         }
     }
 
     fn number<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
         match tokens {
-            [Token::Number(head), tokens @ ..] => Ok((Node::number(*head), tokens)),
-            _ => Err(Error::Expected(vec![Token::number(0)])),
+            [Token::Number(head, literal), rest @ ..] => {
+                let node = if *literal == Literal::PLAIN {
+                    Node::number(*head)
+                } else {
+                    Node::typed_number(*head, literal_type(*head, literal))
+                };
+                Ok((node.at(self.span(tokens)), rest))
+            }
+            _ => Err(Error::Expected(vec![Token::number(0)], self.span(tokens))),
+        }
+    }
+
+    /// adjacent string literals are one literal
+    fn string<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        let mut s = vec![];
+        let mut tokens = tokens;
+        while let [Token::Str(head), rest @ ..] = tokens {
+            s.extend_from_slice(head);
+            tokens = rest;
         }
+        Ok((Node::string(s).at(span), tokens))
     }
 
     fn in_paren<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
@@ -52,36 +430,174 @@ impl Parser {
                 let (node, tokens) = self.expr(tokens)?;
                 match tokens {
                     [Token::RightParen, tokens @ ..] => Ok((node, tokens)),
-                    _ => Err(Error::Expected(vec![Token::RightParen])),
+                    _ => Err(Error::Expected(vec![Token::RightParen], self.span(tokens))),
                 }
             }
-            _ => Err(Error::Expected(vec![Token::LeftParen])),
+            _ => Err(Error::Expected(vec![Token::LeftParen], self.span(tokens))),
+        }
+    }
+
+    fn _args<'a>(
+        &mut self,
+        tokens: &'a [Token],
+        mut args: Vec<Node>,
+    ) -> Result<(Vec<Node>, &'a [Token])> {
+        let (arg, tokens) = self.assign(tokens)?;
+        args.push(arg);
+        match tokens {
+            [Token::Comma, tokens @ ..] => self._args(tokens, args),
+            [Token::RightParen, tokens @ ..] => Ok((args, tokens)),
+            _ => Err(Error::Expected(
+                vec![Token::Comma, Token::RightParen],
+                self.span(tokens),
+            )),
+        }
+    }
+    fn args<'a>(&mut self, tokens: &'a [Token]) -> Result<(Vec<Node>, &'a [Token])> {
+        match tokens {
+            [Token::RightParen, tokens @ ..] => Ok((vec![], tokens)),
+            _ => self._args(tokens, vec![]),
+        }
+    }
+
+    fn call<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        match tokens {
+            [Token::Identity(name), Token::LeftParen, rest @ ..] => {
+                let span = self.span(tokens);
+                let (args, tokens) = self.args(rest)?;
+                Ok((Node::call(name, args).at(span), tokens))
+            }
+            _ => Err(Error::Expected(
+                vec![Token::identity(""), Token::LeftParen],
+                self.span(tokens),
+            )),
         }
     }
 
     fn primary<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
-        self.identity(tokens)
-            .or(self.number(tokens))
-            .or(self.in_paren(tokens))
+        match tokens {
+            [Token::Identity(_), Token::LeftParen, ..] => self.call(tokens),
+            [Token::Identity(_), ..] => self.identity(tokens),
+            [Token::Number(..), ..] => self.number(tokens),
+            [Token::Str(_), ..] => self.string(tokens),
+            [Token::LeftParen, ..] => self.in_paren(tokens),
+            _ => Err(Error::Expected(
+                vec![Token::identity(""), Token::number(0), Token::LeftParen],
+                self.span(tokens),
+            )),
+        }
     }
 
     fn unary<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        match tokens {
+            [Token::Plus, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                Ok((Node::positive(node).at(span), tokens))
+            }
+            [Token::Minus, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                Ok((Node::negate(node).at(span), tokens))
+            }
+            [Token::Tilde, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                Ok((Node::bit_not(node).at(span), tokens))
+            }
+            [Token::Ampersand, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                Ok((Node::address(node).at(span), tokens))
+            }
+            [Token::Multiple, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                Ok((Node::dereference(node).at(span), tokens))
+            }
+            [Token::Not, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                Ok((Node::not(node).at(span), tokens))
+            }
+            // ++x is x += 1
+            [Token::Increment, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                let operation = Node::plus(Node::stored().at(span), Node::number(1).at(span));
+                Ok((
+                    Node::compound_assign(node, operation.at(span)).at(span),
+                    tokens,
+                ))
+            }
+            [Token::Decrement, tokens @ ..] => {
+                let (node, tokens) = self.unary(tokens)?;
+                let operation = Node::minus(Node::stored().at(span), Node::number(1).at(span));
+                Ok((
+                    Node::compound_assign(node, operation.at(span)).at(span),
+                    tokens,
+                ))
+            }
+            [Token::LeftParen, rest @ ..]
+                if matches!(
+                    rest,
+                    [Token::Char | Token::Short | Token::Int | Token::Long, ..]
+                ) =>
+            {
+                let (ty, tokens) = self.ty(rest)?;
+                match tokens {
+                    [Token::RightParen, tokens @ ..] => {
+                        let (node, tokens) = self.unary(tokens)?;
+                        Ok((Node::cast(node, ty).at(span), tokens))
+                    }
+                    _ => Err(Error::Expected(vec![Token::RightParen], self.span(tokens))),
+                }
+            }
+            _ => self.postfix(tokens),
+        }
+    }
+
+    fn _postfix<'a>(&mut self, tokens: &'a [Token], node: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
-            [Token::Plus, Token::Number(n), tokens @ ..] => Ok((Node::number(*n), tokens)),
-            [Token::Minus, Token::Number(n), tokens @ ..] => Ok((Node::number(*n * -1), tokens)),
-            _ => self.primary(tokens),
+            // a[i] is *(a + i)
+            [Token::LeftBracket, tokens @ ..] => {
+                let (index, tokens) = self.expr(tokens)?;
+                match tokens {
+                    [Token::RightBracket, tokens @ ..] => {
+                        let element = Node::dereference(Node::plus(node, index).at(span));
+                        self._postfix(tokens, element.at(span))
+                    }
+                    _ => Err(Error::Expected(
+                        vec![Token::RightBracket],
+                        self.span(tokens),
+                    )),
+                }
+            }
+            [Token::Increment, tokens @ ..] => {
+                let operation = Node::plus(Node::stored().at(span), Node::number(1).at(span));
+                self._postfix(tokens, Node::post_assign(node, operation.at(span)).at(span))
+            }
+            [Token::Decrement, tokens @ ..] => {
+                let operation = Node::minus(Node::stored().at(span), Node::number(1).at(span));
+                self._postfix(tokens, Node::post_assign(node, operation.at(span)).at(span))
+            }
+            _ => Ok((node, tokens)),
         }
     }
+    fn postfix<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (node, tokens) = self.primary(tokens)?;
+        self._postfix(tokens, node)
+    }
 
     fn _multiple<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
             [Token::Multiple, tokens @ ..] => {
                 let (right, tokens) = self.unary(tokens)?;
-                self._multiple(tokens, Node::multiple(left, right))
+                self._multiple(tokens, Node::multiple(left, right).at(span))
             }
             [Token::Devide, tokens @ ..] => {
                 let (right, tokens) = self.unary(tokens)?;
-                self._multiple(tokens, Node::devide(left, right))
+                self._multiple(tokens, Node::devide(left, right).at(span))
+            }
+            [Token::Percent, tokens @ ..] => {
+                let (right, tokens) = self.unary(tokens)?;
+                self._multiple(tokens, Node::modulo(left, right).at(span))
             }
             _ => Ok((left, tokens)),
         }
@@ -92,14 +608,15 @@ impl Parser {
     }
 
     fn _add<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
             [Token::Plus, tokens @ ..] => {
                 let (right, tokens) = self.multiple(tokens)?;
-                self._add(tokens, Node::plus(left, right))
+                self._add(tokens, Node::plus(left, right).at(span))
             }
             [Token::Minus, tokens @ ..] => {
                 let (right, tokens) = self.multiple(tokens)?;
-                self._add(tokens, Node::minus(left, right))
+                self._add(tokens, Node::minus(left, right).at(span))
             }
             _ => Ok((left, tokens)),
         }
@@ -110,41 +627,62 @@ impl Parser {
         self._add(tokens, left)
     }
 
+    fn _shift<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        match tokens {
+            [Token::ShiftLeft, tokens @ ..] => {
+                let (right, tokens) = self.add(tokens)?;
+                self._shift(tokens, Node::shift_left(left, right).at(span))
+            }
+            [Token::ShiftRight, tokens @ ..] => {
+                let (right, tokens) = self.add(tokens)?;
+                self._shift(tokens, Node::shift_right(left, right).at(span))
+            }
+            _ => Ok((left, tokens)),
+        }
+    }
+    fn shift<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (left, tokens) = self.add(tokens)?;
+        self._shift(tokens, left)
+    }
+
     fn _relational<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
             [Token::More, tokens @ ..] => {
-                let (right, tokens) = self.add(tokens)?;
-                self._relational(tokens, Node::less(right, left))
+                let (right, tokens) = self.shift(tokens)?;
+                self._relational(tokens, Node::less(right, left).at(span))
             }
             [Token::Less, tokens @ ..] => {
-                let (right, tokens) = self.add(tokens)?;
-                self._relational(tokens, Node::less(left, right))
+                let (right, tokens) = self.shift(tokens)?;
+                self._relational(tokens, Node::less(left, right).at(span))
             }
             [Token::MoreEqual, tokens @ ..] => {
-                let (right, tokens) = self.add(tokens)?;
-                self._relational(tokens, Node::less_equal(right, left))
+                let (right, tokens) = self.shift(tokens)?;
+                self._relational(tokens, Node::less_equal(right, left).at(span))
             }
             [Token::LessEqual, tokens @ ..] => {
-                let (right, tokens) = self.add(tokens)?;
-                self._relational(tokens, Node::less_equal(left, right))
+                let (right, tokens) = self.shift(tokens)?;
+                self._relational(tokens, Node::less_equal(left, right).at(span))
             }
             _ => Ok((left, tokens)),
         }
     }
     fn relational<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
-        let (left, tokens) = self.add(tokens)?;
+        let (left, tokens) = self.shift(tokens)?;
         self._relational(tokens, left)
     }
 
     fn _equality<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
             [Token::Equal, tokens @ ..] => {
                 let (right, tokens) = self.relational(tokens)?;
-                self._equality(tokens, Node::equal(right, left))
+                self._equality(tokens, Node::equal(right, left).at(span))
             }
             [Token::NotEqual, tokens @ ..] => {
                 let (right, tokens) = self.relational(tokens)?;
-                self._equality(tokens, Node::unequal(left, right))
+                self._equality(tokens, Node::unequal(left, right).at(span))
             }
             _ => Ok((left, tokens)),
         }
@@ -154,196 +692,737 @@ impl Parser {
         self._equality(tokens, left)
     }
 
-    fn assign_right<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+    fn _bit_and<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
-            [Token::Assign, tokens @ ..] => self.assign(tokens),
-            _ => Err(Error::Expected(vec![Token::Assign])),
+            [Token::Ampersand, tokens @ ..] => {
+                let (right, tokens) = self.equality(tokens)?;
+                self._bit_and(tokens, Node::bit_and(left, right).at(span))
+            }
+            _ => Ok((left, tokens)),
         }
     }
-    fn assign<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+    fn bit_and<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
         let (left, tokens) = self.equality(tokens)?;
-        let right = self.assign_right(tokens);
-        match (left, right) {
-            (Node::LocalVariable(id, offset), Ok((right, tokens))) => Ok((
-                Node::assign(Node::local_variable(id, offset), right),
-                tokens,
-            )),
-            (left, Err(..)) => Ok((left, tokens)),
-            (_, _) => unimplemented!("expect left is local variable"),
-        }
+        self._bit_and(tokens, left)
     }
 
-    fn expr<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
-        self.assign(tokens)
+    fn _bit_xor<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        match tokens {
+            [Token::Caret, tokens @ ..] => {
+                let (right, tokens) = self.bit_and(tokens)?;
+                self._bit_xor(tokens, Node::bit_xor(left, right).at(span))
+            }
+            _ => Ok((left, tokens)),
+        }
+    }
+    fn bit_xor<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (left, tokens) = self.bit_and(tokens)?;
+        self._bit_xor(tokens, left)
     }
 
-    fn return_n<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
-        let (node, tokens) = self.expr(tokens)?;
+    fn _bit_or<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
-            [Token::EndExpr, tail @ ..] => Ok((Node::return_n(node), tail)),
-            _ => Err(Error::Expected(vec![Token::EndExpr])),
+            [Token::Pipe, tokens @ ..] => {
+                let (right, tokens) = self.bit_xor(tokens)?;
+                self._bit_or(tokens, Node::bit_or(left, right).at(span))
+            }
+            _ => Ok((left, tokens)),
         }
     }
+    fn bit_or<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (left, tokens) = self.bit_xor(tokens)?;
+        self._bit_or(tokens, left)
+    }
 
-    fn if_else_body<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+    fn _logical_and<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
         match tokens {
-            [Token::Else, tokens @ ..] => self.stmt(tokens),
-            _ => Err(Error::Expected(vec![Token::Else])),
+            [Token::And, tokens @ ..] => {
+                let (right, tokens) = self.bit_or(tokens)?;
+                self._logical_and(tokens, Node::and(left, right).at(span))
+            }
+            _ => Ok((left, tokens)),
         }
     }
-    fn if_n<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
-        let (condition, tokens) = self.in_paren(tokens)?;
-        let (body, tokens) = self.stmt(tokens)?;
+    fn logical_and<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (left, tokens) = self.bit_or(tokens)?;
+        self._logical_and(tokens, left)
+    }
 
-        if let Ok((else_body, tokens)) = self.if_else_body(tokens) {
-            Ok((Node::if_n(condition, body, Some(else_body)), tokens))
-        } else {
-            Ok((Node::if_n(condition, body, None), tokens))
+    fn _logical_or<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        match tokens {
+            [Token::Or, tokens @ ..] => {
+                let (right, tokens) = self.logical_and(tokens)?;
+                self._logical_or(tokens, Node::or(left, right).at(span))
+            }
+            _ => Ok((left, tokens)),
         }
     }
+    fn logical_or<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (left, tokens) = self.logical_and(tokens)?;
+        self._logical_or(tokens, left)
+    }
 
-    fn for_condition<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+    fn conditional<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (condition, tokens) = self.logical_or(tokens)?;
+        let span = self.span(tokens);
         match tokens {
-            [Token::EndExpr, tokens @ ..] => Err(Error::Expected(vec![])),
-            _ => {
-                let (node, tokens) = self.expr(tokens)?;
+            [Token::Question, tokens @ ..] => {
+                let (then, tokens) = self.expr(tokens)?;
                 match tokens {
-                    [Token::EndExpr, tokens @ ..] => Ok((node, tokens)),
-                    _ => Err(Error::Expected(vec![Token::EndExpr])),
+                    [Token::Colon, tokens @ ..] => {
+                        let (else_n, tokens) = self.conditional(tokens)?;
+                        Ok((Node::conditional(condition, then, else_n).at(span), tokens))
+                    }
+                    _ => Err(Error::Expected(vec![Token::Colon], self.span(tokens))),
                 }
             }
+            _ => Ok((condition, tokens)),
         }
     }
-    fn for_condition_third<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+
+    fn assign<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (left, tokens) = self.conditional(tokens)?;
+        let span = self.span(tokens);
         match tokens {
-            [Token::RightParen, _tokens @ ..] => Err(Error::Expected(vec![])),
-            _ => {
-                let (node, tokens) = self.expr(tokens)?;
-                match tokens {
-                    [Token::RightParen, tokens @ ..] => Ok((node, tokens)),
-                    _ => Err(Error::Expected(vec![Token::RightParen])),
-                }
+            [Token::Assign, tokens @ ..] => {
+                let (right, tokens) = self.assign(tokens)?;
+                Ok((Node::assign(left, right).at(span), tokens))
             }
+            [token, rest @ ..] => match compound_operator(token) {
+                Some(operator) => {
+                    let (right, tokens) = self.assign(rest)?;
+                    let operation = operator(Node::stored().at(span), right).at(span);
+                    Ok((Node::compound_assign(left, operation).at(span), tokens))
+                }
+                None => Ok((left, tokens)),
+            },
+            _ => Ok((left, tokens)),
         }
     }
-    fn for_n<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
-        match tokens {
-            [Token::LeftParen, tokens @ ..] => {
-                let (condition1, tokens) = if let Ok((cnd, tokens)) = self.for_condition(tokens) {
-                    (Some(cnd), tokens)
-                } else {
-                    (None, tokens)
-                };
-                let (condition2, tokens) = if let Ok((cnd, tokens)) = self.for_condition(tokens) {
-                    (Some(cnd), tokens)
-                } else {
-                    (None, tokens)
-                };
-                let (condition3, tokens) =
-                    if let Ok((cnd, tokens)) = self.for_condition_third(tokens) {
-                        (Some(cnd), tokens)
-                    } else {
-                        (None, tokens)
-                    };
 
-                let (body, tokens) = self.stmt(tokens)?;
-                Ok((
-                    Node::for_n(condition1, condition2, condition3, body),
-                    tokens,
-                ))
+    fn _expr<'a>(&mut self, tokens: &'a [Token], left: Node) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        match tokens {
+            [Token::Comma, tokens @ ..] => {
+                let (right, tokens) = self.assign(tokens)?;
+                self._expr(tokens, Node::comma(left, right).at(span))
             }
-            _ => Err(Error::Expected(vec![Token::LeftParen])),
+            _ => Ok((left, tokens)),
+        }
+    }
+    fn expr<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (left, tokens) = self.assign(tokens)?;
+        self._expr(tokens, left)
+    }
+
+    fn return_n<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        let (node, tokens) = self.expr(tokens)?;
+        match tokens {
+            [Token::EndExpr, tail @ ..] => Ok((Node::return_n(node).at(span), tail)),
+            _ => Err(Error::Expected(vec![Token::EndExpr], self.span(tokens))),
         }
     }
 
-    fn while_n<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+    fn if_n<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
         let (condition, tokens) = self.in_paren(tokens)?;
         let (body, tokens) = self.stmt(tokens)?;
-        Ok((Node::while_n(condition, body), tokens))
-    }
 
-    fn block<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
-        let mut nodes = vec![];
-        let mut tokens = tokens;
-        loop {
-            match tokens {
-                [Token::RightBlock, tokens @ ..] => {
-                    return Ok((Node::block(nodes), tokens));
-                }
-                [_, _tokens @ ..] => match self.stmt(tokens) {
-                    Ok((node, _tokens)) => {
-                        nodes.push(node);
-                        tokens = _tokens
-                    }
-                    Err(e) => return Err(e),
-                },
-                _ => return Err(Error::Expected(vec![Token::RightBlock])),
+        match tokens {
+            [Token::Else, tokens @ ..] => {
+                let (else_body, tokens) = self.stmt(tokens)?;
+                Ok((
+                    Node::if_n(condition, body, Some(else_body)).at(span),
+                    tokens,
+                ))
             }
+            _ => Ok((Node::if_n(condition, body, None).at(span), tokens)),
         }
     }
 
-    fn stmt<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+    /// an optional expression in the parentheses of for and the token after it
+    fn for_clause<'a>(
+        &mut self,
+        tokens: &'a [Token],
+        end: Token,
+    ) -> Result<(Option<Node>, &'a [Token])> {
         match tokens {
-            [Token::LeftBlock, tokens @ ..] => self.block(tokens),
-            [Token::Return, tokens @ ..] => self.return_n(tokens),
-            [Token::If, tokens @ ..] => self.if_n(tokens),
-            [Token::For, tokens @ ..] => self.for_n(tokens),
-            [Token::While, tokens @ ..] => self.while_n(tokens),
+            [token, tokens @ ..] if *token == end => Ok((None, tokens)),
             _ => {
                 let (node, tokens) = self.expr(tokens)?;
                 match tokens {
-                    [Token::EndExpr, tail @ ..] => Ok((node, tail)),
-                    _ => Err(Error::Expected(vec![Token::EndExpr])),
+                    [token, tokens @ ..] if *token == end => Ok((Some(node), tokens)),
+                    _ => Err(Error::Expected(vec![end], self.span(tokens))),
                 }
             }
         }
     }
-
-    pub fn program<'a>(&mut self, tokens: &'a [Token]) -> Result<(Vec<Node>, &'a [Token])> {
-        let mut tokens = tokens;
-        let mut stmts = vec![];
-        while tokens.len() > 0 {
-            match self.stmt(tokens) {
-                Ok((node, _tokens)) => {
-                    tokens = _tokens;
-                    stmts.push(node);
-                }
-                Err(e) => {
-                    return Err(e);
-                }
+    fn for_n<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        match tokens {
+            [Token::LeftParen, tokens @ ..] => {
+                let (init, tokens) = self.for_clause(tokens, Token::EndExpr)?;
+                let (condition, tokens) = self.for_clause(tokens, Token::EndExpr)?;
+                let (step, tokens) = self.for_clause(tokens, Token::RightParen)?;
+                let (body, tokens) = self.loop_body(tokens)?;
+                Ok((Node::for_n(init, condition, step, body).at(span), tokens))
             }
+            _ => Err(Error::Expected(vec![Token::LeftParen], self.span(tokens))),
         }
+    }
 
-        Ok((stmts, tokens))
+    fn while_n<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        let (condition, tokens) = self.in_paren(tokens)?;
+        let (body, tokens) = self.loop_body(tokens)?;
+        Ok((Node::while_n(condition, body).at(span), tokens))
     }
-}
 
-#[cfg(test)]
+    /// the statement in which break and continue jump in the loop
+    fn loop_body<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        self.breakables.push(Breakable::Loop);
+        let result = self.stmt(tokens);
+        self.breakables.pop();
+        result
+    }
+
+    fn do_while<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        let (body, tokens) = self.loop_body(tokens)?;
+        match tokens {
+            [Token::While, tokens @ ..] => {
+                let (condition, tokens) = self.in_paren(tokens)?;
+                match tokens {
+                    [Token::EndExpr, tokens @ ..] => {
+                        Ok((Node::do_while(body, condition).at(span), tokens))
+                    }
+                    _ => Err(Error::Expected(vec![Token::EndExpr], self.span(tokens))),
+                }
+            }
+            _ => Err(Error::Expected(vec![Token::While], self.span(tokens))),
+        }
+    }
+
+    fn switch<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        let (condition, tokens) = self.in_paren(tokens)?;
+        self.breakables.push(Breakable::Switch);
+        let result = self.stmt(tokens);
+        self.breakables.pop();
+        let (body, tokens) = result?;
+        Ok((
+            Node::switch(condition, body, vec![], false).at(span),
+            tokens,
+        ))
+    }
+
+    /// the statement after the ":" of case or default
+    fn labeled<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        match tokens {
+            [Token::Colon, tokens @ ..] => self.stmt(tokens),
+            _ => Err(Error::Expected(vec![Token::Colon], self.span(tokens))),
+        }
+    }
+
+    fn case<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        if !self.breakables.contains(&Breakable::Switch) {
+            return Err(Error::Misplaced(Token::Case, span));
+        }
+        let (value, tokens) = self.conditional(tokens)?;
+        let (node, tokens) = self.labeled(tokens)?;
+        Ok((Node::case(value, node).at(span), tokens))
+    }
+
+    fn default<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        if !self.breakables.contains(&Breakable::Switch) {
+            return Err(Error::Misplaced(Token::Default, span));
+        }
+        let (node, tokens) = self.labeled(tokens)?;
+        Ok((Node::default(node).at(span), tokens))
+    }
+
+    fn label<'a>(
+        &mut self,
+        name: &str,
+        tokens: &'a [Token],
+        span: Span,
+    ) -> Result<(Node, &'a [Token])> {
+        // the statement is parsed even when the label is a duplicate
+        match self.labels.get(name) {
+            Some(previous) => {
+                let e = Error::DuplicateLabel(name.into(), span, *previous);
+                self.report(e);
+            }
+            None => {
+                self.labels.insert(name.into(), span);
+            }
+        }
+        let (node, tokens) = self.stmt(tokens)?;
+        Ok((Node::label(name, node).at(span), tokens))
+    }
+
+    fn goto<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        let name_span = self.span(tokens);
+        match tokens {
+            [Token::Identity(name), Token::EndExpr, tokens @ ..] => {
+                self.gotos.push((name.clone(), name_span));
+                Ok((Node::goto(name).at(span), tokens))
+            }
+            [Token::Identity(_), ..] => Err(Error::Expected(
+                vec![Token::EndExpr],
+                self.span(&tokens[1..]),
+            )),
+            _ => Err(Error::Expected(
+                vec![Token::identity("")],
+                self.span(tokens),
+            )),
+        }
+    }
+
+    /// reports the gotos to the labels which are not in the function
+    fn resolve_labels(&mut self) {
+        let gotos = std::mem::take(&mut self.gotos);
+        for (name, span) in gotos {
+            if !self.labels.contains_key(&name) {
+                self.report(Error::UndefinedLabel(name, span));
+            }
+        }
+    }
+
+    fn break_n<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        if self.breakables.is_empty() {
+            return Err(Error::Misplaced(Token::Break, span));
+        }
+        match tokens {
+            [Token::EndExpr, tokens @ ..] => Ok((Node::break_n().at(span), tokens)),
+            _ => Err(Error::Expected(vec![Token::EndExpr], self.span(tokens))),
+        }
+    }
+
+    fn continue_n<'a>(&mut self, tokens: &'a [Token], span: Span) -> Result<(Node, &'a [Token])> {
+        if !self.breakables.contains(&Breakable::Loop) {
+            return Err(Error::Misplaced(Token::Continue, span));
+        }
+        match tokens {
+            [Token::EndExpr, tokens @ ..] => Ok((Node::continue_n().at(span), tokens)),
+            _ => Err(Error::Expected(vec![Token::EndExpr], self.span(tokens))),
+        }
+    }
+
+    fn block<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let stack_size = self.enter_scope();
+        let result = self.block_items(tokens);
+        self.leave_scope(stack_size);
+        result
+    }
+
+    fn block_items<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        let mut nodes = vec![];
+        let mut tokens = tokens;
+        loop {
+            match tokens {
+                [Token::RightBlock, tokens @ ..] => {
+                    return Ok((Node::block(nodes).at(span), tokens));
+                }
+                [_, _tokens @ ..] => match self.stmt(tokens) {
+                    Ok((node, _tokens)) => {
+                        nodes.push(node);
+                        tokens = _tokens
+                    }
+                    // the statement is dropped and the parse goes on from the next one
+                    Err(e) => {
+                        tokens = self.synchronize(tokens, &e);
+                        self.report(e);
+                    }
+                },
+                _ => return Err(Error::Expected(vec![Token::RightBlock], self.span(tokens))),
+            }
+        }
+    }
+
+    fn _ty<'a>(&mut self, tokens: &'a [Token], ty: Type) -> Result<(Type, &'a [Token])> {
+        match tokens {
+            [Token::Multiple, tokens @ ..] => self._ty(tokens, Type::pointer(ty)),
+            _ => Ok((ty, tokens)),
+        }
+    }
+    fn ty<'a>(&mut self, tokens: &'a [Token]) -> Result<(Type, &'a [Token])> {
+        match tokens {
+            [Token::Char, tokens @ ..] => self._ty(tokens, Type::Char),
+            [Token::Short, Token::Int, tokens @ ..] | [Token::Short, tokens @ ..] => {
+                self._ty(tokens, Type::Short)
+            }
+            [Token::Int, tokens @ ..] => self._ty(tokens, Type::Int),
+            [Token::Long, Token::Long, Token::Int, tokens @ ..]
+            | [Token::Long, Token::Long, tokens @ ..]
+            | [Token::Long, Token::Int, tokens @ ..]
+            | [Token::Long, tokens @ ..] => self._ty(tokens, Type::Long),
+            _ => Err(Error::Expected(
+                vec![Token::Char, Token::Short, Token::Int, Token::Long],
+                self.span(tokens),
+            )),
+        }
+    }
+
+    /// the dimensions after the identity of a declaration
+    fn array_of<'a>(&mut self, tokens: &'a [Token], ty: Type) -> Result<(Type, &'a [Token])> {
+        match tokens {
+            [Token::LeftBracket, Token::Number(n, _), Token::RightBracket, rest @ ..] => {
+                // the inner dimensions make the element type
+                let (of, rest) = self.array_of(rest, ty)?;
+                // the difference of two pointers in the array has to fit in isize
+                match usize::try_from(*n)
+                    .ok()
+                    .and_then(|n| n.checked_mul(of.size()))
+                {
+                    Some(size) if size <= isize::MAX as usize => {
+                        Ok((Type::array(of, *n as usize), rest))
+                    }
+                    _ => Err(Error::ArrayTooLarge(self.span(&tokens[1..]))),
+                }
+            }
+            [Token::LeftBracket, Token::Number(..), tokens @ ..] => Err(Error::Expected(
+                vec![Token::RightBracket],
+                self.span(tokens),
+            )),
+            [Token::LeftBracket, tokens @ ..] => {
+                Err(Error::Expected(vec![Token::number(0)], self.span(tokens)))
+            }
+            _ => Ok((ty, tokens)),
+        }
+    }
+
+    fn declaration<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (ty, tokens) = self.ty(tokens)?;
+        let span = self.span(tokens);
+        match tokens {
+            [Token::Identity(id), tokens @ ..] => {
+                let (ty, tokens) = self.array_of(tokens, ty)?;
+                match tokens {
+                    [Token::EndExpr, tokens @ ..] => {
+                        self.declare(id, ty, span)?;
+                        Ok((Node::block(vec![]).at(span), tokens))
+                    }
+                    [Token::Assign, tokens @ ..] => {
+                        let variable = self.declare(id, ty, span)?;
+                        let (node, tokens) = self.assign(tokens)?;
+                        match tokens {
+                            [Token::EndExpr, tokens @ ..] => {
+                                Ok((Node::assign(variable, node).at(span), tokens))
+                            }
+                            _ => Err(Error::Expected(vec![Token::EndExpr], self.span(tokens))),
+                        }
+                    }
+                    _ => Err(Error::Expected(
+                        vec![Token::EndExpr, Token::Assign],
+                        self.span(tokens),
+                    )),
+                }
+            }
+            _ => Err(Error::Expected(
+                vec![Token::identity("")],
+                self.span(tokens),
+            )),
+        }
+    }
+
+    fn stmt<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let span = self.span(tokens);
+        match tokens {
+            [Token::Char | Token::Short | Token::Int | Token::Long, ..] => self.declaration(tokens),
+            [Token::LeftBlock, tokens @ ..] => self.block(tokens),
+            [Token::Return, tokens @ ..] => self.return_n(tokens, span),
+            [Token::If, tokens @ ..] => self.if_n(tokens, span),
+            [Token::For, tokens @ ..] => self.for_n(tokens, span),
+            [Token::While, tokens @ ..] => self.while_n(tokens, span),
+            // the null statement
+            [Token::EndExpr, tokens @ ..] => Ok((Node::block(vec![]).at(span), tokens)),
+            [Token::Do, tokens @ ..] => self.do_while(tokens, span),
+            [Token::Switch, tokens @ ..] => self.switch(tokens, span),
+            [Token::Case, tokens @ ..] => self.case(tokens, span),
+            [Token::Default, tokens @ ..] => self.default(tokens, span),
+            [Token::Identity(name), Token::Colon, tokens @ ..] => self.label(name, tokens, span),
+            [Token::Goto, tokens @ ..] => self.goto(tokens, span),
+            [Token::Break, tokens @ ..] => self.break_n(tokens, span),
+            [Token::Continue, tokens @ ..] => self.continue_n(tokens, span),
+            _ => {
+                let (node, tokens) = self.expr(tokens)?;
+                match tokens {
+                    [Token::EndExpr, tail @ ..] => Ok((node, tail)),
+                    _ => Err(Error::Expected(vec![Token::EndExpr], self.span(tokens))),
+                }
+            }
+        }
+    }
+
+    fn _params<'a>(
+        &mut self,
+        tokens: &'a [Token],
+        mut params: Vec<Node>,
+    ) -> Result<(Vec<Node>, &'a [Token])> {
+        let (ty, tokens) = self.ty(tokens)?;
+        let span = self.span(tokens);
+        match tokens {
+            [Token::Identity(id), tokens @ ..] => {
+                // a parameter declared as an array is a pointer to the element,
+                // so the outermost dimension may be omitted
+                let (ty, tokens) = match tokens {
+                    [Token::LeftBracket, Token::RightBracket, tokens @ ..] => {
+                        let (of, tokens) = self.array_of(tokens, ty)?;
+                        (Type::pointer(of), tokens)
+                    }
+                    _ => match self.array_of(tokens, ty)? {
+                        (Type::Array(of, _), tokens) => (Type::Pointer(of), tokens),
+                        (ty, tokens) => (ty, tokens),
+                    },
+                };
+                params.push(self.declare(id, ty, span)?);
+                match tokens {
+                    [Token::Comma, tokens @ ..] => self._params(tokens, params),
+                    [Token::RightParen, tokens @ ..] => Ok((params, tokens)),
+                    _ => Err(Error::Expected(
+                        vec![Token::Comma, Token::RightParen],
+                        self.span(tokens),
+                    )),
+                }
+            }
+            _ => Err(Error::Expected(
+                vec![Token::identity("")],
+                self.span(tokens),
+            )),
+        }
+    }
+    fn params<'a>(&mut self, tokens: &'a [Token]) -> Result<(Vec<Node>, &'a [Token])> {
+        match tokens {
+            [Token::RightParen, tokens @ ..] | [Token::Void, Token::RightParen, tokens @ ..] => {
+                Ok((vec![], tokens))
+            }
+            _ => self._params(tokens, vec![]),
+        }
+    }
+
+    fn function<'a>(
+        &mut self,
+        name: &str,
+        ty: Type,
+        tokens: &'a [Token],
+        span: Span,
+    ) -> Result<(Node, &'a [Token])> {
+        // every function has its own stack frame
+        // and the parameters share the scope with the outermost block of the body
+        self.stack_size = 0;
+        self.frame_size = 0;
+        self.oversized = None;
+        // and its own labels
+        self.labels.clear();
+        self.gotos.clear();
+        let stack_size = self.enter_scope();
+        let result = self.function_rest(name, ty, tokens, span);
+        self.leave_scope(stack_size);
+        result
+    }
+
+    fn _initializer<'a>(
+        &mut self,
+        tokens: &'a [Token],
+        mut items: Vec<Node>,
+        span: Span,
+    ) -> Result<(Node, &'a [Token])> {
+        let (item, tokens) = self.initializer(tokens)?;
+        items.push(item);
+        match tokens {
+            [Token::Comma, Token::RightBlock, tokens @ ..] | [Token::RightBlock, tokens @ ..] => {
+                Ok((Node::initializer_list(items).at(span), tokens))
+            }
+            [Token::Comma, tokens @ ..] => self._initializer(tokens, items, span),
+            _ => Err(Error::Expected(
+                vec![Token::Comma, Token::RightBlock],
+                self.span(tokens),
+            )),
+        }
+    }
+    fn initializer<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        match tokens {
+            [Token::LeftBlock, rest @ ..] => self._initializer(rest, vec![], self.span(tokens)),
+            _ => self.assign(tokens),
+        }
+    }
+
+    /// the variable is visible from its initializer.
+    /// the outermost dimension may be omitted when the initializer decides it
+    fn global<'a>(
+        &mut self,
+        name: &str,
+        ty: Type,
+        tokens: &'a [Token],
+        span: Span,
+    ) -> Result<(Node, &'a [Token])> {
+        let (ty, tokens, omitted) = match tokens {
+            [Token::LeftBracket, Token::RightBracket, tokens @ ..] => {
+                let (of, tokens) = self.array_of(tokens, ty)?;
+                (Type::array(of, 0), tokens, true)
+            }
+            _ => {
+                let (ty, tokens) = self.array_of(tokens, ty)?;
+                (ty, tokens, false)
+            }
+        };
+        self.declare_global(name, ty.clone(), span)?;
+        let (init, tokens) = match tokens {
+            [Token::Assign, tokens @ ..] => {
+                let (init, tokens) = self.initializer(tokens)?;
+                (Some(init), tokens)
+            }
+            _ => (None, tokens),
+        };
+        let ty = match (ty, &init) {
+            (Type::Array(of, _), Some(init)) if omitted => {
+                let ty = Type::array((*of).clone(), length(init, &of));
+                self.globals
+                    .insert(name.into(), (External::Variable(ty.clone()), span));
+                ty
+            }
+            (_, None) if omitted => return Err(Error::MissingSize(name.into(), span)),
+            (ty, _) => ty,
+        };
+        match tokens {
+            [Token::EndExpr, tokens @ ..] => Ok((Node::global_definition(name, ty, init), tokens)),
+            _ if init.is_some() => Err(Error::Expected(vec![Token::EndExpr], self.span(tokens))),
+            _ => Err(Error::Expected(
+                vec![Token::EndExpr, Token::Assign],
+                self.span(tokens),
+            )),
+        }
+    }
+
+    /// a function or a variable at file scope
+    fn external<'a>(&mut self, tokens: &'a [Token]) -> Result<(Node, &'a [Token])> {
+        let (ty, tokens) = self.ty(tokens)?;
+        let span = self.span(tokens);
+        let result = match tokens {
+            [Token::Identity(name), Token::LeftParen, tokens @ ..] => {
+                self.function(name, ty, tokens, span)
+            }
+            [Token::Identity(name), tokens @ ..] => self.global(name, ty, tokens, span),
+            _ => Err(Error::Expected(
+                vec![Token::identity("")],
+                self.span(tokens),
+            )),
+        };
+        result.map(|(node, tokens)| (node.at(span), tokens))
+    }
+
+    fn function_rest<'a>(
+        &mut self,
+        name: &str,
+        ty: Type,
+        tokens: &'a [Token],
+        span: Span,
+    ) -> Result<(Node, &'a [Token])> {
+        // () leaves the parameters unspecified but (void) declares none
+        let prototyped = !matches!(tokens, [Token::RightParen, ..]);
+        let (params, tokens) = self.params(tokens)?;
+        let types = params
+            .iter()
+            .map(|p| match &p.kind {
+                NodeKind::LocalVariable(_, _, ty) => ty.clone(),
+                _ => unreachable!("expect parameter"),
+            })
+            .collect();
+        let defined = matches!(tokens, [Token::LeftBlock, ..]);
+        self.declare_function(name, ty.clone(), prototyped.then_some(types), defined, span)?;
+        match tokens {
+            [Token::LeftBlock, tokens @ ..] => {
+                let (body, tokens) = self.block_items(tokens)?;
+                self.resolve_labels();
+                // the whole function is parsed, so the parser goes on after it
+                if let Some(span) = self.oversized {
+                    self.report(Error::FrameTooLarge(span));
+                }
+                // rsp stays 16 byte aligned after the prologue
+                let frame_size = align_to(self.frame_size, 16);
+                Ok((
+                    Node::function(name, ty, params, prototyped, Some(body), frame_size),
+                    tokens,
+                ))
+            }
+            [Token::EndExpr, tokens @ ..] => Ok((
+                Node::function(name, ty, params, prototyped, None, 0),
+                tokens,
+            )),
+            _ => Err(Error::Expected(
+                vec![Token::LeftBlock, Token::EndExpr],
+                self.span(tokens),
+            )),
+        }
+    }
+
+    /// the functions and the variables which are parsed successfully.
+    /// the others are reported to self.errors.
+    pub fn program(&mut self, tokens: &[Token]) -> Vec<Node> {
+        let mut tokens = tokens;
+        let mut externals = vec![];
+        while !tokens.is_empty() {
+            match self.external(tokens) {
+                Ok((node, _tokens)) => {
+                    tokens = _tokens;
+                    externals.push(node);
+                }
+                Err(e) => {
+                    let rest = self.synchronize(tokens, &e);
+                    self.report(e);
+                    // a "}" out of any block is skipped here,
+                    // with the ";" after it which ends an initializer
+                    tokens = match rest {
+                        [Token::RightBlock, Token::EndExpr, rest @ ..]
+                        | [Token::RightBlock, rest @ ..] => rest,
+                        _ if rest.len() == tokens.len() => &rest[1..],
+                        _ => rest,
+                    };
+                }
+            }
+        }
+        externals
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::super::super::tokenizer::tokenize;
     use super::*;
     #[test]
     fn it_block() {
-        let mut parser = Parser::new();
-        let tokens = tokenize(
+        let (tokens, spans) = tokenize(
             "
-                n = 0;
-                m = 0;
+            int main() {
+                int n = 0;
+                int m = 0;
+                int i;
                 for(i=0; i<1; i=i+1) {
                     n = n + i;
                     m = m + i;
                 }
+            }
             ",
-        );
-        let n = Node::local_variable("n", 8 * 0);
-        let m = Node::local_variable("m", 8 * 1);
-        let i = Node::local_variable("i", 8 * 2);
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let n = Node::local_variable("n", 4, Type::Int);
+        let m = Node::local_variable("m", 4 * 2, Type::Int);
+        let i = Node::local_variable("i", 4 * 3, Type::Int);
         assert_eq!(
             parser.program(&tokens[..]),
-            Ok((
-                vec![
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
                     Node::assign(n.clone(), Node::number(0)),
                     Node::assign(m.clone(), Node::number(0)),
+                    Node::block(vec![]),
                     Node::for_n(
                         Some(Node::assign(i.clone(), Node::number(0))),
                         Some(Node::less(i.clone(), Node::number(1))),
@@ -356,40 +1435,49 @@ mod tests {
                             Node::assign(m.clone(), Node::plus(m.clone(), i.clone())),
                         ]),
                     ),
-                ],
-                &[] as &[Token]
-            ))
+                ])),
+                16
+            )]
         );
     }
 
     #[test]
     fn it_program() {
-        let mut parser = Parser::new();
-        let tokens = tokenize(
+        let (tokens, spans) = tokenize(
             "
-                a = 3;
-                b = 5 * 6 - 8;
+            int main() {
+                int a = 3;
+                int b = 5 * 6 - 8;
                 return a + b / 2;
 
-                n = 0;
-                m = 0;
+                int n = 0;
+                int m = 0;
+                int i;
                 for(i=0; i<1; i=i+1) {
                     n = n + i;
                     m = m + i;
                 }
                 if(1==1) a + b;
                 while(1==1) a + b;
+            }
             ",
-        );
-        let a = Node::local_variable("a", 8 * 0);
-        let b = Node::local_variable("b", 8 * 1);
-        let n = Node::local_variable("n", 8 * 2);
-        let m = Node::local_variable("m", 8 * 3);
-        let i = Node::local_variable("i", 8 * 4);
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let a = Node::local_variable("a", 4, Type::Int);
+        let b = Node::local_variable("b", 4 * 2, Type::Int);
+        let n = Node::local_variable("n", 4 * 3, Type::Int);
+        let m = Node::local_variable("m", 4 * 4, Type::Int);
+        let i = Node::local_variable("i", 4 * 5, Type::Int);
         assert_eq!(
             parser.program(&tokens[..]),
-            Ok((
-                vec![
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
                     Node::assign(a.clone(), Node::number(3)),
                     Node::assign(
                         b.clone(),
@@ -404,6 +1492,7 @@ mod tests {
                     )),
                     Node::assign(n.clone(), Node::number(0)),
                     Node::assign(m.clone(), Node::number(0)),
+                    Node::block(vec![]),
                     Node::for_n(
                         Some(Node::assign(i.clone(), Node::number(0))),
                         Some(Node::less(i.clone(), Node::number(1))),
@@ -425,9 +1514,1025 @@ mod tests {
                         Node::equal(Node::number(1), Node::number(1)),
                         Node::plus(a.clone(), b.clone()),
                     ),
-                ],
-                &[] as &[Token]
-            ))
+                ])),
+                32
+            )]
+        );
+    }
+
+    #[test]
+    fn it_function() {
+        let (tokens, spans) = tokenize(
+            "
+            int add(int a, int b) {
+                return a + b;
+            }
+            int main() {
+                int x = 1;
+                return add(x, add(2, 3)) + zero();
+            }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let a = Node::local_variable("a", 4, Type::Int);
+        let b = Node::local_variable("b", 4 * 2, Type::Int);
+        let x = Node::local_variable("x", 4, Type::Int);
+        assert_eq!(
+            parser.program(&tokens[..]),
+            vec![
+                Node::function(
+                    "add",
+                    Type::Int,
+                    vec![a.clone(), b.clone()],
+                    true,
+                    Some(Node::block(vec![Node::return_n(Node::plus(a, b))])),
+                    16
+                ),
+                Node::function(
+                    "main",
+                    Type::Int,
+                    vec![],
+                    false,
+                    Some(Node::block(vec![
+                        Node::assign(x.clone(), Node::number(1)),
+                        Node::return_n(Node::plus(
+                            Node::call(
+                                "add",
+                                vec![x, Node::call("add", vec![Node::number(2), Node::number(3)])]
+                            ),
+                            Node::call("zero", vec![])
+                        )),
+                    ])),
+                    16
+                ),
+            ]
+        );
+
+        // (void) is the empty list of the parameters
+        let (tokens, spans) = tokenize("int f(void); int main(void) { return f(); }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        assert_eq!(
+            parser.program(&tokens[..]),
+            vec![
+                Node::function("f", Type::Int, vec![], true, None, 0),
+                Node::function(
+                    "main",
+                    Type::Int,
+                    vec![],
+                    true,
+                    Some(Node::block(vec![Node::return_n(Node::call("f", vec![]))])),
+                    0
+                ),
+            ]
+        );
+        assert!(parser.errors.is_empty());
+
+        // () agrees with the parameters which the integer promotion does not change
+        let (tokens, spans) = tokenize(
+            "int f(); int f(long a); int f(); int g(); int g(char c);",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![Error::ConflictingTypes(
+                "g".into(),
+                Span::new(0, 46, 1, 47),
+                Span::new(0, 37, 1, 38)
+            )]
+        );
+
+        // a function is declared any number of times but defined once
+        let (tokens, spans) = tokenize(
+            "int f(int a); int f(int b) { return b; } int f(int c); int f(int d) { return 0; } long f(int e); int f(long g);",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::Redefined("f".into(), Span::new(0, 59, 1, 60), Span::new(0, 18, 1, 19)),
+                Error::ConflictingTypes(
+                    "f".into(),
+                    Span::new(0, 87, 1, 88),
+                    Span::new(0, 18, 1, 19)
+                ),
+                Error::ConflictingTypes(
+                    "f".into(),
+                    Span::new(0, 101, 1, 102),
+                    Span::new(0, 18, 1, 19)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_pointer() {
+        let (tokens, spans) = tokenize(
+            "
+            int main() {
+                int x;
+                int *p;
+                int **pp;
+                p = &x;
+                pp = &p;
+                **pp = *p + 1;
+                return p - (p + 2);
+            }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let x = Node::local_variable("x", 4, Type::Int);
+        let p = Node::local_variable("p", 8 * 2, Type::pointer(Type::Int));
+        let pp = Node::local_variable("pp", 8 * 3, Type::pointer(Type::pointer(Type::Int)));
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::block(vec![]),
+                    Node::block(vec![]),
+                    Node::block(vec![]),
+                    Node::assign(p.clone(), Node::address(x.clone())),
+                    Node::assign(pp.clone(), Node::address(p.clone())),
+                    Node::assign(
+                        Node::dereference(Node::dereference(pp.clone())),
+                        Node::plus(Node::dereference(p.clone()), Node::number(1))
+                    ),
+                    Node::return_n(Node::minus(
+                        p.clone(),
+                        Node::plus(p.clone(), Node::number(2))
+                    )),
+                ])),
+                32
+            )]
+        );
+    }
+
+    #[test]
+    fn it_array() {
+        let (tokens, spans) = tokenize(
+            "
+            int f(int a[], long m[2][3]);
+            int main() {
+                char c;
+                int a[2][3];
+                a[1][c];
+            }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let a = Node::local_variable("a", 28, Type::array(Type::array(Type::Int, 3), 2));
+        let c = Node::local_variable("c", 1, Type::Char);
+        assert_eq!(
+            nodes,
+            vec![
+                // the parameters are pointers to the elements
+                Node::function(
+                    "f",
+                    Type::Int,
+                    vec![
+                        Node::local_variable("a", 8, Type::pointer(Type::Int)),
+                        Node::local_variable("m", 16, Type::pointer(Type::array(Type::Long, 3))),
+                    ],
+                    true,
+                    None,
+                    0
+                ),
+                Node::function(
+                    "main",
+                    Type::Int,
+                    vec![],
+                    false,
+                    Some(Node::block(vec![
+                        Node::block(vec![]),
+                        Node::block(vec![]),
+                        Node::dereference(Node::plus(
+                            Node::dereference(Node::plus(a, Node::number(1))),
+                            c
+                        )),
+                    ])),
+                    32
+                ),
+            ]
+        );
+        assert!(parser.errors.is_empty());
+
+        let (tokens, spans) =
+            tokenize("int main() { int a[2; int b[]; int c[1]; c[0; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::Expected(vec![Token::RightBracket], Span::new(0, 20, 1, 21)),
+                Error::Expected(vec![Token::number(0)], Span::new(0, 28, 1, 29)),
+                Error::Expected(vec![Token::RightBracket], Span::new(0, 44, 1, 45)),
+            ]
+        );
+
+        let (tokens, spans) = tokenize(
+            "int main() { int a[0x7fffffffffffffff]; int b[0xffffffffffffffff]; int c[2][0x1000000000000000][8]; return 0; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::ArrayTooLarge(Span::new(0, 19, 1, 20)),
+                Error::ArrayTooLarge(Span::new(0, 46, 1, 47)),
+                // the inner dimensions are multiplied first
+                Error::ArrayTooLarge(Span::new(0, 76, 1, 77)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_string() {
+        let (tokens, spans) = tokenize(r#"int main() { "ab" "c\n" ""; 'a' + ""[0]; }"#, 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    // adjacent literals are concatenated
+                    Node::string(b"abc\n".to_vec()),
+                    Node::plus(
+                        Node::number(97),
+                        Node::dereference(Node::plus(Node::string(vec![]), Node::number(0)))
+                    ),
+                ])),
+                0
+            )]
+        );
+    }
+
+    #[test]
+    fn it_number() {
+        let (tokens, spans) = tokenize(
+            "int main() { 1; 0x1; 0xffffffff; 1u; 1l; 0x8000000000000000; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    // a plain decimal constant is typed by its value in the semantic analysis
+                    Node::number(1),
+                    Node::typed_number(1, Type::Int),
+                    Node::typed_number(0xffffffff, Type::UnsignedInt),
+                    Node::typed_number(1, Type::UnsignedInt),
+                    Node::typed_number(1, Type::Long),
+                    Node::typed_number(i64::MIN as isize, Type::UnsignedLong),
+                ])),
+                0
+            )]
+        );
+    }
+
+    #[test]
+    fn it_global() {
+        let (tokens, spans) = tokenize(
+            "
+            int g;
+            long a[2] = 1;
+            int main() { int g; g = a[0]; }
+            int f() { return g; }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let a = Node::global_variable("a", Type::array(Type::Long, 2));
+        assert_eq!(
+            nodes,
+            vec![
+                Node::global_definition("g", Type::Int, None),
+                Node::global_definition("a", Type::array(Type::Long, 2), Some(Node::number(1))),
+                // the local variable hides the global one
+                Node::function(
+                    "main",
+                    Type::Int,
+                    vec![],
+                    false,
+                    Some(Node::block(vec![
+                        Node::block(vec![]),
+                        Node::assign(
+                            Node::local_variable("g", 4, Type::Int),
+                            Node::dereference(Node::plus(a, Node::number(0)))
+                        ),
+                    ])),
+                    16
+                ),
+                Node::function(
+                    "f",
+                    Type::Int,
+                    vec![],
+                    false,
+                    Some(Node::block(vec![Node::return_n(Node::global_variable(
+                        "g",
+                        Type::Int
+                    ))])),
+                    0
+                ),
+            ]
+        );
+
+        let (tokens, spans) =
+            tokenize("int g; int g; int h 1; int main() { return h; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::Redeclared("g".into(), Span::new(0, 11, 1, 12), Span::new(0, 4, 1, 5)),
+                Error::Expected(vec![Token::EndExpr, Token::Assign], Span::new(0, 20, 1, 21)),
+            ]
+        );
+
+        // a function and a variable do not share a name
+        let (tokens, spans) = tokenize(
+            "int g; int g() { return 0; } int h(); int h; int f() { return g; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::Redeclared("g".into(), Span::new(0, 11, 1, 12), Span::new(0, 4, 1, 5)),
+                Error::Redeclared("h".into(), Span::new(0, 42, 1, 43), Span::new(0, 33, 1, 34)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_initializer() {
+        let (tokens, spans) = tokenize(
+            "
+            int a[] = {1, {2}, 3,};
+            int m[][2] = {1, 2, 3, {4}, 5};
+            char s[][3] = {\"ab\", \"c\"};
+            char t[] = \"xyz\";
+            int *p = &m[1][0];
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let n = Node::number;
+        let list = Node::initializer_list;
+        let m = Type::array(Type::array(Type::Int, 2), 3);
+        assert_eq!(
+            nodes,
+            vec![
+                Node::global_definition(
+                    "a",
+                    Type::array(Type::Int, 3),
+                    Some(list(vec![n(1), list(vec![n(2)]), n(3)]))
+                ),
+                // the braces around the rows are omitted
+                Node::global_definition(
+                    "m",
+                    m.clone(),
+                    Some(list(vec![n(1), n(2), n(3), list(vec![n(4)]), n(5)]))
+                ),
+                Node::global_definition(
+                    "s",
+                    Type::array(Type::array(Type::Char, 3), 2),
+                    Some(list(vec![
+                        Node::string(b"ab".to_vec()),
+                        Node::string(b"c".to_vec())
+                    ]))
+                ),
+                Node::global_definition(
+                    "t",
+                    Type::array(Type::Char, 4),
+                    Some(Node::string(b"xyz".to_vec()))
+                ),
+                Node::global_definition(
+                    "p",
+                    Type::pointer(Type::Int),
+                    Some(Node::address(Node::dereference(Node::plus(
+                        Node::dereference(Node::plus(Node::global_variable("m", m), n(1))),
+                        n(0)
+                    ))))
+                ),
+            ]
+        );
+
+        let (tokens, spans) = tokenize("int a[]; int b[2] = {1 2};", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::MissingSize("a".into(), Span::new(0, 4, 1, 5)),
+                Error::Expected(
+                    vec![Token::Comma, Token::RightBlock],
+                    Span::new(0, 23, 1, 24)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_declaration() {
+        let (tokens, spans) = tokenize(
+            "
+            long *f(char c, long long l);
+            int main() {
+                char c;
+                long l;
+                short int s = 1;
+                int *p;
+            }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes,
+            vec![
+                Node::function(
+                    "f",
+                    Type::pointer(Type::Long),
+                    vec![
+                        Node::local_variable("c", 1, Type::Char),
+                        Node::local_variable("l", 16, Type::Long),
+                    ],
+                    true,
+                    None,
+                    0
+                ),
+                Node::function(
+                    "main",
+                    Type::Int,
+                    vec![],
+                    false,
+                    Some(Node::block(vec![
+                        Node::block(vec![]),
+                        Node::block(vec![]),
+                        Node::assign(Node::local_variable("s", 18, Type::Short), Node::number(1)),
+                        Node::block(vec![]),
+                    ])),
+                    32
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_scope() {
+        let (tokens, spans) = tokenize(
+            "
+            int main() {
+                int x = 1;
+                {
+                    long x = 2;
+                    int y = x;
+                }
+                {
+                    int z = x;
+                }
+                return x;
+            }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let x = Node::local_variable("x", 4, Type::Int);
+        let inner_x = Node::local_variable("x", 16, Type::Long);
+        let y = Node::local_variable("y", 20, Type::Int);
+        let z = Node::local_variable("z", 8, Type::Int);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::assign(x.clone(), Node::number(1)),
+                    Node::block(vec![
+                        Node::assign(inner_x.clone(), Node::number(2)),
+                        Node::assign(y, inner_x),
+                    ]),
+                    Node::block(vec![Node::assign(z, x.clone())]),
+                    Node::return_n(x),
+                ])),
+                32
+            )]
+        );
+    }
+
+    #[test]
+    fn it_scope_error() {
+        let (tokens, spans) = tokenize("int main() { { int x; } return x; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![Error::Undeclared("x".into(), Span::new(0, 31, 1, 32))]
+        );
+
+        let (tokens, spans) = tokenize("int main() { int x; char x; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![Error::Redeclared(
+                "x".into(),
+                Span::new(0, 25, 1, 26),
+                Span::new(0, 17, 1, 18)
+            )]
+        );
+
+        let (tokens, spans) = tokenize("int f(int a) { int a; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![Error::Redeclared(
+                "a".into(),
+                Span::new(0, 19, 1, 20),
+                Span::new(0, 10, 1, 11)
+            )]
+        );
+
+        let (tokens, spans) = tokenize("int main() {\n  return 1\n}", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors,
+            vec![Error::Expected(
+                vec![Token::EndExpr],
+                Span::new(0, 24, 3, 1)
+            )]
+        );
+    }
+
+    #[test]
+    fn it_logical() {
+        let (tokens, spans) =
+            tokenize("int main() { int a; int b; a || !b && a == b; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let a = Node::local_variable("a", 4, Type::Int);
+        let b = Node::local_variable("b", 8, Type::Int);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::block(vec![]),
+                    Node::block(vec![]),
+                    Node::or(
+                        a.clone(),
+                        Node::and(Node::not(b.clone()), Node::equal(b, a))
+                    ),
+                ])),
+                16
+            )]
+        );
+    }
+
+    #[test]
+    fn it_bitwise() {
+        let (tokens, spans) = tokenize(
+            "int main() { int a; a | a ^ a & a == a; a << 1 + a < a % 2 >> a; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let a = Node::local_variable("a", 4, Type::Int);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::block(vec![]),
+                    Node::bit_or(
+                        a.clone(),
+                        Node::bit_xor(
+                            a.clone(),
+                            Node::bit_and(a.clone(), Node::equal(a.clone(), a.clone()))
+                        )
+                    ),
+                    Node::less(
+                        Node::shift_left(a.clone(), Node::plus(Node::number(1), a.clone())),
+                        Node::shift_right(Node::modulo(a.clone(), Node::number(2)), a)
+                    ),
+                ])),
+                16
+            )]
+        );
+    }
+
+    #[test]
+    fn it_compound_assign() {
+        let (tokens, spans) =
+            tokenize("int main() { int a; a += a <<= 2; a++ - --a; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let a = Node::local_variable("a", 4, Type::Int);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::block(vec![]),
+                    Node::compound_assign(
+                        a.clone(),
+                        Node::plus(
+                            Node::stored(),
+                            Node::compound_assign(
+                                a.clone(),
+                                Node::shift_left(Node::stored(), Node::number(2))
+                            )
+                        )
+                    ),
+                    Node::minus(
+                        Node::post_assign(a.clone(), Node::plus(Node::stored(), Node::number(1))),
+                        Node::compound_assign(a, Node::minus(Node::stored(), Node::number(1)))
+                    ),
+                ])),
+                16
+            )]
+        );
+    }
+
+    #[test]
+    fn it_conditional() {
+        let (tokens, spans) = tokenize(
+            "int main() { int a; a = a ? 1 : a ? 2 : 3, a; a ? a = 1, 2 : 3; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let a = Node::local_variable("a", 4, Type::Int);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::block(vec![]),
+                    Node::comma(
+                        Node::assign(
+                            a.clone(),
+                            Node::conditional(
+                                a.clone(),
+                                Node::number(1),
+                                Node::conditional(a.clone(), Node::number(2), Node::number(3))
+                            )
+                        ),
+                        a.clone()
+                    ),
+                    Node::conditional(
+                        a.clone(),
+                        Node::comma(Node::assign(a, Node::number(1)), Node::number(2)),
+                        Node::number(3)
+                    ),
+                ])),
+                16
+            )]
+        );
+    }
+
+    #[test]
+    fn it_break() {
+        let (tokens, spans) = tokenize(
+            "int main() { while (1) { if (1) break; continue; } break; continue; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![Node::while_n(
+                    Node::number(1),
+                    Node::block(vec![
+                        Node::if_n(Node::number(1), Node::break_n(), None),
+                        Node::continue_n(),
+                    ])
+                )])),
+                0
+            )]
+        );
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::Misplaced(Token::Break, Span::new(0, 51, 1, 52)),
+                Error::Misplaced(Token::Continue, Span::new(0, 58, 1, 59)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_switch() {
+        let (tokens, spans) = tokenize(
+            "int main() { int a; switch (a) { case 1: do a++; while (a); default: break; } case 2: ; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let a = Node::local_variable("a", 4, Type::Int);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::block(vec![]),
+                    Node::switch(
+                        a.clone(),
+                        Node::block(vec![
+                            Node::case(
+                                Node::number(1),
+                                Node::do_while(
+                                    Node::post_assign(
+                                        a.clone(),
+                                        Node::plus(Node::stored(), Node::number(1))
+                                    ),
+                                    a
+                                )
+                            ),
+                            Node::default(Node::break_n()),
+                        ]),
+                        vec![],
+                        false
+                    ),
+                ])),
+                16
+            )]
+        );
+        assert_eq!(
+            parser.errors,
+            vec![Error::Misplaced(Token::Case, Span::new(0, 78, 1, 79))]
+        );
+    }
+
+    #[test]
+    fn it_goto() {
+        let (tokens, spans) = tokenize(
+            "int main() { goto end; end: return 0; }\nint f() { a: a: goto end; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes[0],
+            Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::goto("end"),
+                    Node::label("end", Node::return_n(Node::number(0))),
+                ])),
+                0
+            )
+        );
+        // labels are resolved in each function
+        assert_eq!(
+            parser.errors,
+            vec![
+                Error::DuplicateLabel("a".into(), Span::new(0, 53, 2, 14), Span::new(0, 50, 2, 11)),
+                Error::UndefinedLabel("end".into(), Span::new(0, 61, 2, 22)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_unary() {
+        let (tokens, spans) = tokenize(
+            "int main() { int x; -x; - -3; -(x + 1) * ~+x; !-x; (char *)-(long)x; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        let x = Node::local_variable("x", 4, Type::Int);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::block(vec![]),
+                    Node::negate(x.clone()),
+                    Node::negate(Node::negate(Node::number(3))),
+                    Node::multiple(
+                        Node::negate(Node::plus(x.clone(), Node::number(1))),
+                        Node::bit_not(Node::positive(x.clone()))
+                    ),
+                    Node::not(Node::negate(x.clone())),
+                    Node::cast(
+                        Node::negate(Node::cast(x, Type::Long)),
+                        Type::pointer(Type::Char)
+                    ),
+                ])),
+                16
+            )]
+        );
+    }
+
+    #[test]
+    fn it_for() {
+        let (tokens, spans) = tokenize("int main() { for (;;) break; for (; 1;) ; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            nodes,
+            vec![Node::function(
+                "main",
+                Type::Int,
+                vec![],
+                false,
+                Some(Node::block(vec![
+                    Node::for_n(None, None, None, Node::break_n()),
+                    Node::for_n(None, Some(Node::number(1)), None, Node::block(vec![])),
+                ])),
+                0
+            )]
+        );
+        // an error in the clauses is not mistaken for an empty clause
+        let (tokens, spans) = tokenize("int main() { for (; 1 + ; ) ; }", 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        parser.program(&tokens[..]);
+        assert_eq!(
+            parser.errors[0],
+            Error::Expected(
+                vec![Token::identity(""), Token::number(0), Token::LeftParen],
+                Span::new(0, 24, 1, 25)
+            )
+        );
+    }
+
+    #[test]
+    fn it_recovery() {
+        let (tokens, spans) = tokenize(
+            "
+            int f() {
+                int a = ;
+                a = 1;
+                if (a == ) { a = 2; }
+                return a;
+            }
+            int g( { return 1; }
+            int main() { int b; b = x; return b; }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(
+            parser
+                .errors
+                .iter()
+                .map(|e| e.span().line)
+                .collect::<Vec<_>>(),
+            vec![3, 5, 8, 9]
+        );
+        let a = Node::local_variable("a", 4, Type::Int);
+        let b = Node::local_variable("b", 4, Type::Int);
+        assert_eq!(
+            nodes,
+            vec![
+                Node::function(
+                    "f",
+                    Type::Int,
+                    vec![],
+                    false,
+                    Some(Node::block(vec![
+                        Node::assign(a.clone(), Node::number(1)),
+                        Node::return_n(a),
+                    ])),
+                    16
+                ),
+                Node::function(
+                    "main",
+                    Type::Int,
+                    vec![],
+                    false,
+                    Some(Node::block(vec![Node::block(vec![]), Node::return_n(b)])),
+                    16
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_frame_size() {
+        let mut src = String::from("int main() { char c0;");
+        for i in 0..300 {
+            src.push_str(&format!("long v{} = {};", i, i));
+        }
+        // sibling blocks share their slots, the frame fits the larger one
+        src.push_str("{ char a; long b; } { long c; long d; long e = v299; } return v0; }");
+        let (tokens, spans) = tokenize(&src, 0).unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        match &nodes[0].kind {
+            NodeKind::Function(_, _, _, _, Some(body), frame_size) => {
+                // c0 at 1, v0 at 16 .. v299 at 2408, and e at 2432
+                assert_eq!(*frame_size, 2432);
+                match &body.kind {
+                    NodeKind::Block(nodes) => assert_eq!(
+                        nodes[302],
+                        Node::block(vec![
+                            Node::block(vec![]),
+                            Node::block(vec![]),
+                            Node::assign(
+                                Node::local_variable("e", 2432, Type::Long),
+                                Node::local_variable("v299", 2408, Type::Long)
+                            ),
+                        ])
+                    ),
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        // the error is at the variable which does not fit, and the next function is parsed
+        let (tokens, spans) = tokenize(
+            "int main() { int a[400000000]; long b[99999999]; int c[999999999999]; return 0; } int f() { return 0; }",
+            0,
+        )
+        .unwrap();
+        let mut parser = Parser::new(&spans);
+        let nodes = parser.program(&tokens[..]);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(
+            parser.errors,
+            vec![Error::FrameTooLarge(Span::new(0, 36, 1, 37))]
         );
     }
 }
@@ -0,0 +1,118 @@
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Type {
+    Char,
+
+    Short,
+
+    Int,
+
+    Long,
+
+    UnsignedInt,
+
+    UnsignedLong,
+
+    /// pointer to the type
+    Pointer(Box<Type>),
+
+    /// the number of the elements of the type.
+    /// int a[2][3] is an array of 2 arrays of 3 ints
+    Array(Box<Type>, usize),
+}
+
+impl Type {
+    pub fn pointer(to: Self) -> Self {
+        Type::Pointer(Box::new(to))
+    }
+
+    pub fn array(of: Self, n: usize) -> Self {
+        Type::Array(Box::new(of), n)
+    }
+
+    /// bytes which a value of this type occupies
+    pub fn size(&self) -> usize {
+        match self {
+            Type::Char => 1,
+            Type::Short => 2,
+            Type::Int => 4,
+            Type::Long => 8,
+            Type::UnsignedInt => 4,
+            Type::UnsignedLong => 8,
+            Type::Pointer(_) => 8,
+            Type::Array(of, n) => of.size() * n,
+        }
+    }
+
+    /// a value of this type is placed at an address which is a multiple of this
+    pub fn align(&self) -> usize {
+        match self {
+            Type::Array(of, _) => of.align(),
+            ty => ty.size(),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_signed() || matches!(self, Type::UnsignedInt | Type::UnsignedLong)
+    }
+
+    /// a right shift of a signed value copies the sign bit
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::Char | Type::Short | Type::Int | Type::Long)
+    }
+
+    /// the largest value of an integer type
+    pub fn max(&self) -> u64 {
+        match self {
+            Type::Char => i8::MAX as u64,
+            Type::Short => i16::MAX as u64,
+            Type::Int => i32::MAX as u64,
+            Type::Long => i64::MAX as u64,
+            Type::UnsignedInt => u32::MAX as u64,
+            Type::UnsignedLong => u64::MAX,
+            ty => unreachable!("{} is not an integer type", ty),
+        }
+    }
+
+    /// integer promotion
+    /// char and short are converted to int before arithmetic
+    pub fn promoted(&self) -> Self {
+        match self {
+            Type::Char | Type::Short => Type::Int,
+            ty => ty.clone(),
+        }
+    }
+}
+
+/// the type as it is written in the source
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Char => write!(f, "char"),
+            Type::Short => write!(f, "short"),
+            Type::Int => write!(f, "int"),
+            Type::Long => write!(f, "long"),
+            Type::UnsignedInt => write!(f, "unsigned int"),
+            Type::UnsignedLong => write!(f, "unsigned long"),
+            Type::Pointer(to) if matches!(**to, Type::Pointer(_)) => write!(f, "{}*", to),
+            // int (*)[3]
+            Type::Pointer(to) if matches!(**to, Type::Array(..)) => {
+                let to = to.to_string();
+                let (of, dimensions) = to.split_at(to.find('[').expect("dimension"));
+                write!(f, "{} (*){}", of, dimensions)
+            }
+            Type::Pointer(to) => write!(f, "{} *", to),
+            // the outermost dimension is the first
+            Type::Array(of, n) => {
+                let mut of = of;
+                let mut dimensions = format!("[{}]", n);
+                while let Type::Array(inner, n) = &**of {
+                    dimensions.push_str(&format!("[{}]", n));
+                    of = inner;
+                }
+                write!(f, "{}{}", of, dimensions)
+            }
+        }
+    }
+}
@@ -0,0 +1,1393 @@
+use super::parser::{Node, NodeKind, Type};
+use super::tokenizer::Span;
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// *node where node is not a pointer
+    InvalidDereference(Type, Span),
+
+    /// the operator does not accept operands of these types
+    InvalidOperands(Type, Type, Span),
+
+    /// the unary operator does not accept an operand of the type
+    InvalidOperand(Type, Span),
+
+    /// & or = is applied to a value which has no address
+    NotLvalue(Span),
+
+    /// expected type, actual type, and where the value is
+    IncompatibleTypes(Type, Type, Span),
+
+    /// function name, the number of parameters, the number of arguments,
+    /// and where the call and the function are
+    ArgumentCount(String, usize, usize, Span, Span),
+
+    /// the value of case is not an integer constant expression
+    NotConstant(Span),
+
+    /// the initializer of a variable at file scope is not known at compile time
+    NotConstantInitializer(Span),
+
+    /// an array is initialized with neither a list nor a string literal
+    InvalidInitializer(Span),
+
+    /// the value, and where the case and the previous case with the value are
+    DuplicateCase(isize, Span, Span),
+
+    /// where the default and the previous default in the switch are
+    DuplicateDefault(Span, Span),
+}
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// the program is valid but may not work as expected
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// the function is called without any declaration
+    ImplicitDeclaration(String, Span),
+
+    /// the initializer has more values than the object has elements
+    ExcessElements(Span),
+}
+
+/// the span is filled by Checker::node
+fn typed(kind: NodeKind, ty: Type) -> Node {
+    Node {
+        kind,
+        ty: Some(ty),
+        span: Span::default(),
+    }
+}
+
+fn convert(node: Node, ty: &Type) -> Node {
+    if node.ty() == ty {
+        node
+    } else {
+        Node::cast(node, ty.clone())
+    }
+}
+
+/// the integer promotion of an argument which no parameter type is declared for
+fn promote(node: Node) -> Node {
+    let ty = node.ty().promoted();
+    convert(node, &ty)
+}
+
+/// an array in an expression is converted to the pointer to its first element
+fn decay(node: Node) -> Node {
+    match node.ty.clone() {
+        Some(Type::Array(of, _)) => {
+            let span = node.span;
+            typed(NodeKind::Address(Box::new(node)), Type::Pointer(of)).at(span)
+        }
+        _ => node,
+    }
+}
+
+fn is_lvalue(node: &Node) -> bool {
+    matches!(
+        node.kind,
+        NodeKind::LocalVariable(..)
+            | NodeKind::GlobalVariable(..)
+            | NodeKind::Dereference(..)
+            | NodeKind::Str(..)
+    )
+}
+
+fn is_null_pointer_constant(node: &Node) -> bool {
+    matches!(node.kind, NodeKind::Number(0))
+}
+
+/// the value in the width of the type,
+/// sign extended for a signed type and zero extended for an unsigned type
+fn wrap(n: isize, ty: &Type) -> isize {
+    match (ty.size(), ty.is_signed()) {
+        (1, _) => n as i8 as isize,
+        (2, _) => n as i16 as isize,
+        (4, true) => n as i32 as isize,
+        (4, false) => n as u32 as isize,
+        _ => n,
+    }
+}
+
+/// the value of an integer constant expression,
+/// or None when the value is not known at compile time
+fn constant(node: &Node) -> Option<isize> {
+    let ty = node.ty();
+    if !ty.is_integer() {
+        return None;
+    }
+    let binary = |l: &Node, r: &Node, f: fn(isize, isize) -> Option<isize>| {
+        f(constant(l)?, constant(r)?).map(|n| wrap(n, ty))
+    };
+    // the operands of an unsigned operation are compared and divided as unsigned
+    let unsigned = |l: &Node| !l.ty().is_signed();
+    match &node.kind {
+        NodeKind::Number(n) => Some(*n),
+        NodeKind::Cast(n) => constant(n).map(|n| wrap(n, ty)),
+        NodeKind::Negate(n) => constant(n).map(|n| wrap(n.wrapping_neg(), ty)),
+        NodeKind::BitNot(n) => constant(n).map(|n| wrap(!n, ty)),
+        NodeKind::Not(n) => constant(n).map(|n| (n == 0) as isize),
+        NodeKind::Plus(l, r) => binary(l, r, |l, r| Some(l.wrapping_add(r))),
+        NodeKind::Minus(l, r) => binary(l, r, |l, r| Some(l.wrapping_sub(r))),
+        NodeKind::Multiple(l, r) => binary(l, r, |l, r| Some(l.wrapping_mul(r))),
+        NodeKind::Devide(l, r) if unsigned(l) => binary(l, r, |l, r| {
+            (l as usize).checked_div(r as usize).map(|n| n as isize)
+        }),
+        NodeKind::Devide(l, r) => binary(l, r, |l, r| l.checked_div(r)),
+        NodeKind::Modulo(l, r) if unsigned(l) => binary(l, r, |l, r| {
+            (l as usize).checked_rem(r as usize).map(|n| n as isize)
+        }),
+        NodeKind::Modulo(l, r) => binary(l, r, |l, r| l.checked_rem(r)),
+        NodeKind::BitAnd(l, r) => binary(l, r, |l, r| Some(l & r)),
+        NodeKind::BitOr(l, r) => binary(l, r, |l, r| Some(l | r)),
+        NodeKind::BitXor(l, r) => binary(l, r, |l, r| Some(l ^ r)),
+        NodeKind::ShiftLeft(l, r) => binary(l, r, |l, r| Some(l.wrapping_shl(r as u32))),
+        NodeKind::ShiftRight(l, r) if unsigned(l) => binary(l, r, |l, r| {
+            Some((l as usize).wrapping_shr(r as u32) as isize)
+        }),
+        NodeKind::ShiftRight(l, r) => binary(l, r, |l, r| Some(l.wrapping_shr(r as u32))),
+        NodeKind::Equal(l, r) => binary(l, r, |l, r| Some((l == r) as isize)),
+        NodeKind::UnEqual(l, r) => binary(l, r, |l, r| Some((l != r) as isize)),
+        NodeKind::Less(l, r) if unsigned(l) => {
+            binary(l, r, |l, r| Some(((l as usize) < (r as usize)) as isize))
+        }
+        NodeKind::Less(l, r) => binary(l, r, |l, r| Some((l < r) as isize)),
+        NodeKind::LessEqual(l, r) if unsigned(l) => {
+            binary(l, r, |l, r| Some(((l as usize) <= (r as usize)) as isize))
+        }
+        NodeKind::LessEqual(l, r) => binary(l, r, |l, r| Some((l <= r) as isize)),
+        NodeKind::And(l, r) => match constant(l)? {
+            0 => Some(0),
+            _ => constant(r).map(|r| (r != 0) as isize),
+        },
+        NodeKind::Or(l, r) => match constant(l)? {
+            0 => constant(r).map(|r| (r != 0) as isize),
+            _ => Some(1),
+        },
+        NodeKind::Conditional(condition, then, else_n) => match constant(condition)? {
+            0 => constant(else_n),
+            _ => constant(then),
+        },
+        _ => None,
+    }
+}
+
+/// the object whose address a pointer constant is and the offset in bytes from it,
+/// or None when the address is not known at link time
+fn address_constant(node: &Node) -> Option<(Node, isize)> {
+    let scale = match node.ty() {
+        // pointer arithmetic is scaled by the size of the pointed type
+        Type::Pointer(to) => to.size() as isize,
+        // an integer as wide as a pointer can hold an address
+        ty if ty.is_integer() && ty.size() == 8 => 1,
+        _ => return None,
+    };
+    match &node.kind {
+        NodeKind::Address(n) => match &n.kind {
+            NodeKind::GlobalVariable(..) | NodeKind::Str(_) => Some(((**n).clone(), 0)),
+            // &*p and &a[i]
+            NodeKind::Dereference(p) => address_constant(p),
+            _ => None,
+        },
+        NodeKind::Plus(l, r) => match (address_constant(l), address_constant(r)) {
+            (Some((base, offset)), None) => {
+                Some((base, offset.wrapping_add(constant(r)?.wrapping_mul(scale))))
+            }
+            (None, Some((base, offset))) => {
+                Some((base, offset.wrapping_add(constant(l)?.wrapping_mul(scale))))
+            }
+            _ => None,
+        },
+        NodeKind::Minus(l, r) => {
+            let (base, offset) = address_constant(l)?;
+            Some((base, offset.wrapping_sub(constant(r)?.wrapping_mul(scale))))
+        }
+        NodeKind::Cast(n) => address_constant(n),
+        _ => None,
+    }
+}
+
+/// the constant which a value in the initializer of a variable at file scope is replaced with
+fn fold(node: &Node) -> Option<Node> {
+    let ty = node.ty().clone();
+    if let Some(n) = constant(node) {
+        return Some(typed(NodeKind::Number(n), ty));
+    }
+    match &node.kind {
+        // the null pointer, or an integer converted to a pointer
+        NodeKind::Cast(n) if n.ty().is_integer() => {
+            constant(n).map(|n| typed(NodeKind::Number(n), ty))
+        }
+        _ => {
+            let (base, offset) = address_constant(node)?;
+            let address = typed(NodeKind::Address(Box::new(base)), ty.clone());
+            Some(match offset {
+                0 => address,
+                offset => typed(
+                    NodeKind::Plus(
+                        Box::new(address),
+                        Box::new(typed(NodeKind::Number(offset), Type::Long)),
+                    ),
+                    ty,
+                ),
+            })
+        }
+    }
+}
+
+fn is_char_array(ty: &Type) -> bool {
+    matches!(ty, Type::Array(of, _) if **of == Type::Char)
+}
+
+/// the implicit conversion applied by assignment, argument passing and return
+fn assignment_conversion(node: Node, ty: &Type) -> Result<Node> {
+    match (ty, node.ty()) {
+        (to, from) if to.is_integer() && from.is_integer() => Ok(convert(node, to)),
+        (to @ Type::Pointer(_), from @ Type::Pointer(_)) if to == from => Ok(node),
+        (Type::Pointer(_), _) if is_null_pointer_constant(&node) => Ok(convert(node, ty)),
+        (to, from) => Err(Error::IncompatibleTypes(
+            to.clone(),
+            from.clone(),
+            node.span,
+        )),
+    }
+}
+
+/// integer promotion, and then the operands are converted to the type
+/// which comes first in unsigned long, long, unsigned int and int.
+/// long can represent every unsigned int
+fn usual_arithmetic_conversion(left: Node, right: Node, span: Span) -> Result<(Node, Node, Type)> {
+    match (left.ty().promoted(), right.ty().promoted()) {
+        (l, r) if l.is_integer() && r.is_integer() => {
+            let ty = [Type::UnsignedLong, Type::Long, Type::UnsignedInt, Type::Int]
+                .into_iter()
+                .find(|ty| *ty == l || *ty == r)
+                .expect("promoted");
+            Ok((convert(left, &ty), convert(right, &ty), ty))
+        }
+        (l, r) => Err(Error::InvalidOperands(l, r, span)),
+    }
+}
+
+/// a switch statement whose body is being checked
+struct Switch {
+    /// the type of the condition after the integer promotion
+    ty: Type,
+
+    /// the value and the position of every case so far
+    cases: Vec<(isize, Span)>,
+
+    default: Option<Span>,
+}
+
+pub struct Checker {
+    /// return type, parameter types and the position of every function in the program.
+    /// the parameter types are None when they are unspecified
+    functions: HashMap<String, (Type, Option<Vec<Type>>, Span)>,
+
+    /// return type of the function being checked
+    return_ty: Type,
+
+    /// type of the target of the compound assignment being checked
+    stored: Option<Type>,
+
+    /// the switch statements enclosing the current position, the innermost is the last
+    switches: Vec<Switch>,
+
+    pub warnings: Vec<Warning>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+            return_ty: Type::Int,
+            stored: None,
+            switches: vec![],
+            warnings: vec![],
+        }
+    }
+
+    fn comparison(&mut self, left: Node, right: Node, span: Span) -> Result<(Node, Node)> {
+        let (left, right) = (self.node(left)?, self.node(right)?);
+        match (left.ty().clone(), right.ty().clone()) {
+            (l, r) if l.is_integer() && r.is_integer() => {
+                let (left, right, _) = usual_arithmetic_conversion(left, right, span)?;
+                Ok((left, right))
+            }
+            (l @ Type::Pointer(_), r @ Type::Pointer(_)) if l == r => Ok((left, right)),
+            (ty @ Type::Pointer(_), _) if is_null_pointer_constant(&right) => {
+                Ok((left, convert(right, &ty)))
+            }
+            (_, ty @ Type::Pointer(_)) if is_null_pointer_constant(&left) => {
+                Ok((convert(left, &ty), right))
+            }
+            (l, r) => Err(Error::InvalidOperands(l, r, span)),
+        }
+    }
+
+    /// the operand of an arithmetic unary operator after the integer promotion
+    fn integer_operand(&mut self, n: Node, span: Span) -> Result<(Node, Type)> {
+        let n = self.node(n)?;
+        match n.ty().promoted() {
+            ty if ty.is_integer() => Ok((convert(n, &ty), ty)),
+            ty => Err(Error::InvalidOperand(ty, span)),
+        }
+    }
+
+    /// the operands of ?: converted to the type of the result
+    fn branches(&mut self, then: Node, else_n: Node, span: Span) -> Result<(Node, Node, Type)> {
+        let (then, else_n) = (self.node(then)?, self.node(else_n)?);
+        match (then.ty().clone(), else_n.ty().clone()) {
+            (l, r) if l.is_integer() && r.is_integer() => {
+                usual_arithmetic_conversion(then, else_n, span)
+            }
+            (l @ Type::Pointer(_), r @ Type::Pointer(_)) if l == r => Ok((then, else_n, l)),
+            (ty @ Type::Pointer(_), _) if is_null_pointer_constant(&else_n) => {
+                let else_n = convert(else_n, &ty);
+                Ok((then, else_n, ty))
+            }
+            (_, ty @ Type::Pointer(_)) if is_null_pointer_constant(&then) => {
+                Ok((convert(then, &ty), else_n, ty))
+            }
+            (l, r) => Err(Error::InvalidOperands(l, r, span)),
+        }
+    }
+
+    /// the target and the operation converted to the type of the target
+    fn compound_assign(
+        &mut self,
+        target: Node,
+        operation: Node,
+        span: Span,
+    ) -> Result<(Node, Node)> {
+        let target = self.node(target)?;
+        if !is_lvalue(&target) {
+            return Err(Error::NotLvalue(span));
+        }
+        let ty = target.ty().clone();
+        let outer = self.stored.replace(ty.clone());
+        let operation = self.node(operation);
+        self.stored = outer;
+        let operation = assignment_conversion(operation?, &ty)?;
+        Ok((target, operation))
+    }
+
+    fn switch(&mut self, condition: Node, body: Node, span: Span) -> Result<Node> {
+        let (condition, ty) = self.integer_operand(condition, span)?;
+        self.switches.push(Switch {
+            ty,
+            cases: vec![],
+            default: None,
+        });
+        let body = self.node(body);
+        let switch = self.switches.pop().expect("switch");
+        let cases = switch.cases.into_iter().map(|(value, _)| value).collect();
+        Ok(Node::switch(
+            condition,
+            body?,
+            cases,
+            switch.default.is_some(),
+        ))
+    }
+
+    /// the value is converted to the type of the condition of the switch
+    fn case(&mut self, value: Node, node: Node, span: Span) -> Result<Node> {
+        let value_span = value.span;
+        let value = self.node(value)?;
+        let switch = self.switches.last_mut().expect("case in a switch");
+        let n = match constant(&value) {
+            Some(n) => wrap(n, &switch.ty),
+            None => return Err(Error::NotConstant(value_span)),
+        };
+        if let Some((_, previous)) = switch.cases.iter().find(|(value, _)| *value == n) {
+            return Err(Error::DuplicateCase(n, span, *previous));
+        }
+        switch.cases.push((n, span));
+        let value = typed(NodeKind::Number(n), switch.ty.clone()).at(value_span);
+        Ok(Node::case(value, self.node(node)?))
+    }
+
+    fn default(&mut self, node: Node, span: Span) -> Result<Node> {
+        let switch = self.switches.last_mut().expect("default in a switch");
+        if let Some(previous) = switch.default {
+            return Err(Error::DuplicateDefault(span, previous));
+        }
+        switch.default = Some(span);
+        Ok(Node::default(self.node(node)?))
+    }
+
+    fn arithmetic(&mut self, left: Node, right: Node, span: Span) -> Result<(Node, Node, Type)> {
+        let (left, right) = (self.node(left)?, self.node(right)?);
+        usual_arithmetic_conversion(left, right, span)
+    }
+
+    /// the operands are promoted separately and the result has the type of the left
+    fn shift(&mut self, left: Node, right: Node, span: Span) -> Result<(Node, Node, Type)> {
+        let (left, right) = (self.node(left)?, self.node(right)?);
+        match (left.ty().promoted(), right.ty().promoted()) {
+            (l, r) if l.is_integer() && r.is_integer() => {
+                Ok((convert(left, &l), convert(right, &r), l))
+            }
+            (l, r) => Err(Error::InvalidOperands(l, r, span)),
+        }
+    }
+
+    fn plus(&mut self, left: Node, right: Node, span: Span) -> Result<Node> {
+        let (left, right) = (self.node(left)?, self.node(right)?);
+        match (left.ty().clone(), right.ty().clone()) {
+            (ty @ Type::Pointer(_), i) if i.is_integer() => {
+                let right = convert(right, &Type::Long);
+                Ok(typed(NodeKind::Plus(Box::new(left), Box::new(right)), ty))
+            }
+            (i, ty @ Type::Pointer(_)) if i.is_integer() => {
+                let left = convert(left, &Type::Long);
+                Ok(typed(NodeKind::Plus(Box::new(left), Box::new(right)), ty))
+            }
+            _ => {
+                let (left, right, ty) = usual_arithmetic_conversion(left, right, span)?;
+                Ok(typed(NodeKind::Plus(Box::new(left), Box::new(right)), ty))
+            }
+        }
+    }
+
+    fn minus(&mut self, left: Node, right: Node, span: Span) -> Result<Node> {
+        let (left, right) = (self.node(left)?, self.node(right)?);
+        match (left.ty().clone(), right.ty().clone()) {
+            (ty @ Type::Pointer(_), i) if i.is_integer() => {
+                let right = convert(right, &Type::Long);
+                Ok(typed(NodeKind::Minus(Box::new(left), Box::new(right)), ty))
+            }
+            // the number of elements between two pointers
+            (l @ Type::Pointer(_), r @ Type::Pointer(_)) if l == r => Ok(typed(
+                NodeKind::Minus(Box::new(left), Box::new(right)),
+                Type::Long,
+            )),
+            _ => {
+                let (left, right, ty) = usual_arithmetic_conversion(left, right, span)?;
+                Ok(typed(NodeKind::Minus(Box::new(left), Box::new(right)), ty))
+            }
+        }
+    }
+
+    fn call(&mut self, name: String, args: Vec<Node>, span: Span) -> Result<Node> {
+        let args = args
+            .into_iter()
+            .map(|arg| self.node(arg))
+            .collect::<Result<Vec<Node>>>()?;
+        if let Some((ty, None, _)) = self.functions.get(&name) {
+            // the arguments of the function whose parameters are unspecified
+            // are passed after the integer promotion
+            let ty = ty.clone();
+            let args = args.into_iter().map(promote).collect();
+            Ok(typed(NodeKind::Call(name, args), ty))
+        } else if let Some((ty, Some(params), declared)) = self.functions.get(&name) {
+            if params.len() != args.len() {
+                return Err(Error::ArgumentCount(
+                    name,
+                    params.len(),
+                    args.len(),
+                    span,
+                    *declared,
+                ));
+            }
+            let ty = ty.clone();
+            let args = args
+                .into_iter()
+                .zip(params.iter())
+                .map(|(arg, param)| assignment_conversion(arg, param))
+                .collect::<Result<Vec<Node>>>()?;
+            Ok(typed(NodeKind::Call(name, args), ty))
+        } else {
+            // a function which is not declared returns int
+            // and takes its arguments after the integer promotion
+            // only the first call is reported
+            let reported = self
+                .warnings
+                .iter()
+                .any(|w| matches!(w, Warning::ImplicitDeclaration(n, _) if *n == name));
+            if !reported {
+                self.warnings
+                    .push(Warning::ImplicitDeclaration(name.clone(), span));
+            }
+            let args = args.into_iter().map(promote).collect();
+            Ok(typed(NodeKind::Call(name, args), Type::Int))
+        }
+    }
+
+    fn function(
+        &mut self,
+        name: String,
+        ty: Type,
+        params: Vec<Node>,
+        prototyped: bool,
+        body: Option<Box<Node>>,
+        frame_size: usize,
+    ) -> Result<Node> {
+        self.return_ty = ty.clone();
+        let body = match body {
+            Some(body) => Some(self.node(*body)?),
+            None => None,
+        };
+        Ok(Node::function(
+            name, ty, params, prototyped, body, frame_size,
+        ))
+    }
+
+    /// the value of a scalar known at compile time
+    fn scalar(&mut self, ty: &Type, init: Node) -> Result<Node> {
+        let span = init.span;
+        let init = assignment_conversion(self.node(init)?, ty)?;
+        match fold(&init) {
+            Some(init) => Ok(init.at(span)),
+            None => Err(Error::NotConstantInitializer(span)),
+        }
+    }
+
+    /// the object of the type from the initializers at the head of the list.
+    /// the braces around an inner array may be omitted
+    fn fill(&mut self, ty: &Type, items: &mut Peekable<IntoIter<Node>>) -> Result<Node> {
+        match ty {
+            Type::Array(of, n) => {
+                let mut elements = vec![];
+                while elements.len() < *n {
+                    let braced = match items.peek().map(|item| &item.kind) {
+                        None => break,
+                        Some(NodeKind::InitializerList(_)) => true,
+                        Some(NodeKind::Str(_)) => is_char_array(of),
+                        Some(_) => false,
+                    };
+                    let element = if braced {
+                        let item = items.next().expect("peeked");
+                        self.initializer(of, item)?
+                    } else {
+                        // the element takes the values from the same list
+                        let span = items.peek().expect("peeked").span;
+                        self.fill(of, items)?.at(span)
+                    };
+                    elements.push(element);
+                }
+                Ok(typed(NodeKind::InitializerList(elements), ty.clone()))
+            }
+            _ => match items.next() {
+                Some(item) => self.initializer(ty, item),
+                None => Ok(typed(NodeKind::Number(0), ty.clone())),
+            },
+        }
+    }
+
+    /// every value in the initializer of the object of the type is folded into a constant
+    fn initializer(&mut self, ty: &Type, init: Node) -> Result<Node> {
+        let span = init.span;
+        match init.kind {
+            // the braces may enclose a string literal for an array of char
+            NodeKind::InitializerList(items)
+                if is_char_array(ty)
+                    && matches!(&items[..], [item] if matches!(item.kind, NodeKind::Str(_))) =>
+            {
+                self.initializer(ty, items.into_iter().next().expect("one item"))
+            }
+            NodeKind::InitializerList(items) => {
+                let mut items = items.into_iter().peekable();
+                let node = self.fill(ty, &mut items)?;
+                if let Some(excess) = items.next() {
+                    self.warnings.push(Warning::ExcessElements(excess.span));
+                }
+                Ok(node.at(span))
+            }
+            // the terminating null character is dropped when the array has no room for it
+            NodeKind::Str(s) if is_char_array(ty) => {
+                if s.len() > ty.size() {
+                    self.warnings.push(Warning::ExcessElements(span));
+                }
+                let elements = s
+                    .iter()
+                    .chain(&[0])
+                    .take(ty.size())
+                    .map(|c| typed(NodeKind::Number(*c as i8 as isize), Type::Char).at(span))
+                    .collect();
+                Ok(typed(NodeKind::InitializerList(elements), ty.clone()).at(span))
+            }
+            _ if matches!(ty, Type::Array(..)) => Err(Error::InvalidInitializer(span)),
+            kind => self.scalar(ty, Node { kind, ..init }),
+        }
+    }
+
+    fn global(&mut self, name: String, ty: Type, init: Option<Box<Node>>) -> Result<Node> {
+        let init = match init {
+            Some(init) => Some(self.initializer(&ty, *init)?),
+            None => None,
+        };
+        Ok(Node::global_definition(name, ty, init))
+    }
+
+    fn node(&mut self, node: Node) -> Result<Node> {
+        self.object(node).map(decay)
+    }
+
+    /// the node is checked but an array is left as it is,
+    /// which only & needs
+    fn object(&mut self, node: Node) -> Result<Node> {
+        let span = node.span;
+        let checked = match node.kind {
+            NodeKind::Number(n) => {
+                let ty = node.ty.unwrap_or(if i32::try_from(n).is_ok() {
+                    Type::Int
+                } else {
+                    Type::Long
+                });
+                Ok(typed(NodeKind::Number(n), ty))
+            }
+            NodeKind::Str(s) => {
+                let ty = Type::array(Type::Char, s.len() + 1);
+                Ok(typed(NodeKind::Str(s), ty))
+            }
+            NodeKind::LocalVariable(id, offset, ty) => {
+                Ok(typed(NodeKind::LocalVariable(id, offset, ty.clone()), ty))
+            }
+            NodeKind::GlobalVariable(id, ty) => {
+                Ok(typed(NodeKind::GlobalVariable(id, ty.clone()), ty))
+            }
+            NodeKind::Address(n) => {
+                let n = self.object(*n)?;
+                if !is_lvalue(&n) {
+                    return Err(Error::NotLvalue(span));
+                }
+                let ty = Type::pointer(n.ty().clone());
+                Ok(typed(NodeKind::Address(Box::new(n)), ty))
+            }
+            NodeKind::Dereference(n) => {
+                let n = self.node(*n)?;
+                match n.ty().clone() {
+                    Type::Pointer(to) => Ok(typed(NodeKind::Dereference(Box::new(n)), *to)),
+                    ty => Err(Error::InvalidDereference(ty, span)),
+                }
+            }
+            NodeKind::Cast(n) => {
+                let n = self.node(*n)?;
+                Ok(Node {
+                    kind: NodeKind::Cast(Box::new(n)),
+                    ty: node.ty,
+                    span,
+                })
+            }
+            NodeKind::Equal(left, right) => {
+                let (left, right) = self.comparison(*left, *right, span)?;
+                Ok(typed(
+                    NodeKind::Equal(Box::new(left), Box::new(right)),
+                    Type::Int,
+                ))
+            }
+            NodeKind::UnEqual(left, right) => {
+                let (left, right) = self.comparison(*left, *right, span)?;
+                Ok(typed(
+                    NodeKind::UnEqual(Box::new(left), Box::new(right)),
+                    Type::Int,
+                ))
+            }
+            NodeKind::Less(left, right) => {
+                let (left, right) = self.comparison(*left, *right, span)?;
+                Ok(typed(
+                    NodeKind::Less(Box::new(left), Box::new(right)),
+                    Type::Int,
+                ))
+            }
+            NodeKind::LessEqual(left, right) => {
+                let (left, right) = self.comparison(*left, *right, span)?;
+                Ok(typed(
+                    NodeKind::LessEqual(Box::new(left), Box::new(right)),
+                    Type::Int,
+                ))
+            }
+            NodeKind::Plus(left, right) => self.plus(*left, *right, span),
+            NodeKind::Minus(left, right) => self.minus(*left, *right, span),
+            NodeKind::Multiple(left, right) => {
+                let (left, right, ty) = self.arithmetic(*left, *right, span)?;
+                Ok(typed(
+                    NodeKind::Multiple(Box::new(left), Box::new(right)),
+                    ty,
+                ))
+            }
+            NodeKind::Devide(left, right) => {
+                let (left, right, ty) = self.arithmetic(*left, *right, span)?;
+                Ok(typed(NodeKind::Devide(Box::new(left), Box::new(right)), ty))
+            }
+            NodeKind::Modulo(left, right) => {
+                let (left, right, ty) = self.arithmetic(*left, *right, span)?;
+                Ok(typed(NodeKind::Modulo(Box::new(left), Box::new(right)), ty))
+            }
+            NodeKind::BitAnd(left, right) => {
+                let (left, right, ty) = self.arithmetic(*left, *right, span)?;
+                Ok(typed(NodeKind::BitAnd(Box::new(left), Box::new(right)), ty))
+            }
+            NodeKind::BitOr(left, right) => {
+                let (left, right, ty) = self.arithmetic(*left, *right, span)?;
+                Ok(typed(NodeKind::BitOr(Box::new(left), Box::new(right)), ty))
+            }
+            NodeKind::BitXor(left, right) => {
+                let (left, right, ty) = self.arithmetic(*left, *right, span)?;
+                Ok(typed(NodeKind::BitXor(Box::new(left), Box::new(right)), ty))
+            }
+            NodeKind::ShiftLeft(left, right) => {
+                let (left, right, ty) = self.shift(*left, *right, span)?;
+                Ok(typed(
+                    NodeKind::ShiftLeft(Box::new(left), Box::new(right)),
+                    ty,
+                ))
+            }
+            NodeKind::ShiftRight(left, right) => {
+                let (left, right, ty) = self.shift(*left, *right, span)?;
+                Ok(typed(
+                    NodeKind::ShiftRight(Box::new(left), Box::new(right)),
+                    ty,
+                ))
+            }
+            // the operands are compared with 0 whatever their types are
+            NodeKind::And(left, right) => Ok(typed(
+                NodeKind::And(Box::new(self.node(*left)?), Box::new(self.node(*right)?)),
+                Type::Int,
+            )),
+            NodeKind::Or(left, right) => Ok(typed(
+                NodeKind::Or(Box::new(self.node(*left)?), Box::new(self.node(*right)?)),
+                Type::Int,
+            )),
+            NodeKind::Not(n) => Ok(typed(NodeKind::Not(Box::new(self.node(*n)?)), Type::Int)),
+            NodeKind::Conditional(condition, then, else_n) => {
+                let condition = self.node(*condition)?;
+                let (then, else_n, ty) = self.branches(*then, *else_n, span)?;
+                Ok(typed(
+                    NodeKind::Conditional(Box::new(condition), Box::new(then), Box::new(else_n)),
+                    ty,
+                ))
+            }
+            NodeKind::Comma(left, right) => {
+                let (left, right) = (self.node(*left)?, self.node(*right)?);
+                let ty = right.ty().clone();
+                Ok(typed(NodeKind::Comma(Box::new(left), Box::new(right)), ty))
+            }
+            // the cast is kept even to the same type so that +x is not an lvalue
+            NodeKind::Positive(n) => {
+                let n = self.node(*n)?;
+                match n.ty().promoted() {
+                    ty if ty.is_integer() => Ok(typed(NodeKind::Cast(Box::new(n)), ty)),
+                    ty => Err(Error::InvalidOperand(ty, span)),
+                }
+            }
+            NodeKind::Negate(n) => {
+                let (n, ty) = self.integer_operand(*n, span)?;
+                Ok(typed(NodeKind::Negate(Box::new(n)), ty))
+            }
+            NodeKind::BitNot(n) => {
+                let (n, ty) = self.integer_operand(*n, span)?;
+                Ok(typed(NodeKind::BitNot(Box::new(n)), ty))
+            }
+            NodeKind::Assign(left, right) => {
+                let left = self.node(*left)?;
+                if !is_lvalue(&left) {
+                    return Err(Error::NotLvalue(span));
+                }
+                let ty = left.ty().clone();
+                let right = assignment_conversion(self.node(*right)?, &ty)?;
+                Ok(typed(NodeKind::Assign(Box::new(left), Box::new(right)), ty))
+            }
+            NodeKind::CompoundAssign(target, operation) => {
+                let (target, operation) = self.compound_assign(*target, *operation, span)?;
+                let ty = target.ty().clone();
+                Ok(typed(
+                    NodeKind::CompoundAssign(Box::new(target), Box::new(operation)),
+                    ty,
+                ))
+            }
+            NodeKind::PostAssign(target, operation) => {
+                let (target, operation) = self.compound_assign(*target, *operation, span)?;
+                let ty = target.ty().clone();
+                Ok(typed(
+                    NodeKind::PostAssign(Box::new(target), Box::new(operation)),
+                    ty,
+                ))
+            }
+            NodeKind::Stored => {
+                let ty = self
+                    .stored
+                    .clone()
+                    .expect("Stored out of a compound assignment");
+                Ok(typed(NodeKind::Stored, ty))
+            }
+            NodeKind::Return(n) => {
+                let ty = self.return_ty.clone();
+                let n = assignment_conversion(self.node(*n)?, &ty)?;
+                Ok(Node::return_n(n))
+            }
+            NodeKind::If(condition, then, else_body) => Ok(Node::if_n(
+                self.node(*condition)?,
+                self.node(*then)?,
+                match else_body {
+                    Some(else_body) => Some(self.node(*else_body)?),
+                    None => None,
+                },
+            )),
+            NodeKind::For(condition1, condition2, condition3, body) => {
+                let mut check = |c: Option<Box<Node>>| match c {
+                    Some(c) => self.node(*c).map(Some),
+                    None => Ok(None),
+                };
+                let condition1 = check(condition1)?;
+                let condition2 = check(condition2)?;
+                let condition3 = check(condition3)?;
+                Ok(Node::for_n(
+                    condition1,
+                    condition2,
+                    condition3,
+                    self.node(*body)?,
+                ))
+            }
+            NodeKind::While(condition, body) => {
+                Ok(Node::while_n(self.node(*condition)?, self.node(*body)?))
+            }
+            NodeKind::DoWhile(body, condition) => {
+                Ok(Node::do_while(self.node(*body)?, self.node(*condition)?))
+            }
+            NodeKind::Switch(condition, body, _, _) => self.switch(*condition, *body, span),
+            NodeKind::Case(value, node) => self.case(*value, *node, span),
+            NodeKind::Default(node) => self.default(*node, span),
+            NodeKind::Label(name, node) => Ok(Node::label(name, self.node(*node)?)),
+            NodeKind::Goto(name) => Ok(Node::goto(name)),
+            NodeKind::Break => Ok(Node::break_n()),
+            NodeKind::Continue => Ok(Node::continue_n()),
+            NodeKind::Block(nodes) => Ok(Node::block(
+                nodes
+                    .into_iter()
+                    .map(|n| self.node(n))
+                    .collect::<Result<Vec<Node>>>()?,
+            )),
+            NodeKind::Function(name, ty, params, prototyped, body, frame_size) => {
+                self.function(name, ty, params, prototyped, body, frame_size)
+            }
+            NodeKind::Call(name, args) => self.call(name, args, span),
+            NodeKind::GlobalDefinition(name, ty, init) => self.global(name, ty, init),
+            NodeKind::InitializerList(_) => unreachable!("a list is only in an initializer"),
+        }?;
+        Ok(checked.at(span))
+    }
+
+    pub fn program(&mut self, nodes: Vec<Node>) -> Result<Vec<Node>> {
+        // every function can be called before its definition
+        for node in nodes.iter() {
+            if let NodeKind::Function(name, ty, params, prototyped, _, _) = &node.kind {
+                // a declaration with () does not hide the parameters declared before
+                if !prototyped && self.functions.contains_key(name) {
+                    continue;
+                }
+                let params = params.iter().map(|p| match &p.kind {
+                    NodeKind::LocalVariable(_, _, ty) => ty.clone(),
+                    _ => unreachable!("expect parameter"),
+                });
+                let params = prototyped.then(|| params.collect());
+                self.functions
+                    .insert(name.clone(), (ty.clone(), params, node.span));
+            }
+        }
+        nodes.into_iter().map(|n| self.node(n)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::parse;
+    use super::super::tokenizer::tokenize;
+    use super::*;
+
+    fn body(src: &str) -> Result<Vec<Node>> {
+        let (tokens, spans) = tokenize(src, 0).unwrap();
+        let nodes = Checker::new().program(parse(&tokens, &spans).0)?;
+        match nodes.into_iter().last().map(|n| n.kind) {
+            Some(NodeKind::Function(_, _, _, _, Some(body), _)) => match body.kind {
+                NodeKind::Block(nodes) => Ok(nodes),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn it_usual_arithmetic_conversion() {
+        let nodes = body("int main() { char c; long l; c + l; c * c; }").unwrap();
+        let c = typed(
+            NodeKind::LocalVariable("c".into(), 1, Type::Char),
+            Type::Char,
+        );
+        let l = typed(
+            NodeKind::LocalVariable("l".into(), 16, Type::Long),
+            Type::Long,
+        );
+        assert_eq!(
+            nodes[2],
+            typed(
+                NodeKind::Plus(Box::new(Node::cast(c.clone(), Type::Long)), Box::new(l)),
+                Type::Long
+            )
+        );
+        assert_eq!(
+            nodes[3],
+            typed(
+                NodeKind::Multiple(
+                    Box::new(Node::cast(c.clone(), Type::Int)),
+                    Box::new(Node::cast(c, Type::Int))
+                ),
+                Type::Int
+            )
+        );
+    }
+
+    #[test]
+    fn it_positive() {
+        let nodes = body("int main() { char c; int x; +c; +x; }").unwrap();
+        let c = typed(
+            NodeKind::LocalVariable("c".into(), 1, Type::Char),
+            Type::Char,
+        );
+        assert_eq!(nodes[2], Node::cast(c, Type::Int));
+        assert_eq!(nodes[3].ty(), &Type::Int);
+        assert_eq!(
+            body("int main() { int x; +x = 1; }"),
+            Err(Error::NotLvalue(Span::new(0, 23, 1, 24)))
+        );
+        assert_eq!(
+            body("int main() { int *p; +p; }"),
+            Err(Error::InvalidOperand(
+                Type::pointer(Type::Int),
+                Span::new(0, 21, 1, 22)
+            ))
+        );
+    }
+
+    #[test]
+    fn it_unsigned() {
+        let nodes = body(
+            "int main() { long l; -1 < 0u; l + 1u; 1u + 0x100000000; 0xffffffffu + 1; -1u >> 4; }",
+        )
+        .unwrap();
+        // unsigned int comes before int, long before unsigned int
+        match &nodes[1].kind {
+            NodeKind::Less(l, _) => assert_eq!(l.ty(), &Type::UnsignedInt),
+            _ => unreachable!(),
+        }
+        assert_eq!(constant(&nodes[1]), Some(0));
+        assert_eq!(nodes[2].ty(), &Type::Long);
+        assert_eq!(nodes[3].ty(), &Type::Long);
+        assert_eq!(constant(&nodes[4]), Some(0));
+        assert_eq!(constant(&nodes[5]), Some(0x0fffffff));
+    }
+
+    #[test]
+    fn it_shift() {
+        // the result has the promoted type of the left operand
+        let nodes = body("int main() { char c; long l; c << l; l >> c; c & l; }").unwrap();
+        assert_eq!(nodes[2].ty(), &Type::Int);
+        assert_eq!(nodes[3].ty(), &Type::Long);
+        assert_eq!(nodes[4].ty(), &Type::Long);
+        assert!(body("int main() { int *p; p % 2; }").is_err());
+        assert!(body("int main() { int *p; 1 << p; }").is_err());
+    }
+
+    #[test]
+    fn it_compound_assign() {
+        let nodes = body("int main() { char c; long l; c += l; }").unwrap();
+        let c = typed(
+            NodeKind::LocalVariable("c".into(), 1, Type::Char),
+            Type::Char,
+        );
+        let l = typed(
+            NodeKind::LocalVariable("l".into(), 16, Type::Long),
+            Type::Long,
+        );
+        // the operation is done in long and converted back to char
+        assert_eq!(
+            nodes[2],
+            typed(
+                NodeKind::CompoundAssign(
+                    Box::new(c),
+                    Box::new(Node::cast(
+                        typed(
+                            NodeKind::Plus(
+                                Box::new(Node::cast(
+                                    typed(NodeKind::Stored, Type::Char),
+                                    Type::Long
+                                )),
+                                Box::new(l)
+                            ),
+                            Type::Long
+                        ),
+                        Type::Char
+                    ))
+                ),
+                Type::Char
+            )
+        );
+        let nodes = body("int main() { int *p; p++; }").unwrap();
+        assert_eq!(nodes[1].ty(), &Type::pointer(Type::Int));
+        assert!(body("int main() { int *p; p *= 2; }").is_err());
+    }
+
+    #[test]
+    fn it_conditional() {
+        let nodes = body("int main() { int *p; long l; 1 ? 2 : l; 1 ? p : 0; l, p; }").unwrap();
+        assert_eq!(nodes[2].ty(), &Type::Long);
+        assert_eq!(nodes[3].ty(), &Type::pointer(Type::Int));
+        assert_eq!(nodes[4].ty(), &Type::pointer(Type::Int));
+        assert!(body("int main() { int *p; 1 ? p : 1; }").is_err());
+    }
+
+    #[test]
+    fn it_switch() {
+        let nodes = body(
+            "int main() { char c; switch (c) { case 1 + 2 * 3: default: case -1: case 3 / 2: ; } }",
+        )
+        .unwrap();
+        match &nodes[1].kind {
+            NodeKind::Switch(_, _, cases, default) => {
+                assert_eq!(cases, &vec![7, -1, 1]);
+                assert!(default);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            body("int main() { int x; switch (x) { case 1: case 3 - 2: ; } }"),
+            Err(Error::DuplicateCase(
+                1,
+                Span::new(0, 41, 1, 42),
+                Span::new(0, 33, 1, 34)
+            ))
+        );
+        assert_eq!(
+            body("int main() { int x; switch (x) { case x: ; } }"),
+            Err(Error::NotConstant(Span::new(0, 38, 1, 39)))
+        );
+        assert!(body("int main() { int x; switch (x) { default: default: ; } }").is_err());
+    }
+
+    #[test]
+    fn it_pointer() {
+        let nodes =
+            body("int main() { int x; int *p; int **pp; pp = &p; *pp = &x; p + 1; p - p; **pp; }")
+                .unwrap();
+        assert_eq!(nodes[5].ty(), &Type::pointer(Type::Int));
+        assert_eq!(nodes[6].ty(), &Type::Long);
+        assert_eq!(nodes[7].ty(), &Type::Int);
+    }
+
+    #[test]
+    fn it_array() {
+        let nodes =
+            body("int main() { int a[2][3]; int *p; a; a[1]; a[1][2]; &a; p = a[0]; }").unwrap();
+        let a = typed(
+            NodeKind::LocalVariable("a".into(), 24, Type::array(Type::array(Type::Int, 3), 2)),
+            Type::array(Type::array(Type::Int, 3), 2),
+        );
+        // the array decays to the pointer to its first element
+        assert_eq!(
+            nodes[2],
+            typed(
+                NodeKind::Address(Box::new(a)),
+                Type::pointer(Type::array(Type::Int, 3))
+            )
+        );
+        assert_eq!(nodes[3].ty(), &Type::pointer(Type::Int));
+        assert_eq!(nodes[4].ty(), &Type::Int);
+        assert_eq!(
+            nodes[5].ty(),
+            &Type::pointer(Type::array(Type::array(Type::Int, 3), 2))
+        );
+        assert_eq!(nodes[6].ty(), &Type::pointer(Type::Int));
+        // an array is not assignable
+        assert_eq!(
+            body("int main() { int a[2]; int *p; a = p; }"),
+            Err(Error::NotLvalue(Span::new(0, 33, 1, 34)))
+        );
+        assert!(body("int main() { int a[2]; int *p; p = &a; }").is_err());
+    }
+
+    #[test]
+    fn it_string() {
+        let nodes = body(r#"int main() { char *s; s = "ab"; &"ab"; "ab"[1]; }"#).unwrap();
+        assert_eq!(nodes[1].ty(), &Type::pointer(Type::Char));
+        assert_eq!(nodes[2].ty(), &Type::pointer(Type::array(Type::Char, 3)));
+        assert_eq!(nodes[3].ty(), &Type::Char);
+        assert!(body(r#"int main() { "ab" = 0; }"#).is_err());
+    }
+
+    #[test]
+    fn it_global() {
+        let (tokens, spans) =
+            tokenize("char c = 1 + 255; long l; int x = 3; int y = x;", 0).unwrap();
+        let nodes = parse(&tokens, &spans).0;
+        let mut checker = Checker::new();
+        // the initializer is folded in the type of the variable
+        assert_eq!(
+            checker.node(nodes[0].clone()),
+            Ok(Node::global_definition(
+                "c",
+                Type::Char,
+                Some(typed(NodeKind::Number(0), Type::Char))
+            ))
+        );
+        assert_eq!(
+            checker.node(nodes[1].clone()),
+            Ok(Node::global_definition("l", Type::Long, None))
+        );
+        assert_eq!(
+            checker.node(nodes[3].clone()),
+            Err(Error::NotConstantInitializer(Span::new(0, 45, 1, 46)))
+        );
+    }
+
+    #[test]
+    fn it_initializer() {
+        let (tokens, spans) = tokenize(
+            "
+            int m[2][2] = {1, {2}, 3};
+            char s[3] = \"abcd\";
+            long l;
+            long *p = &l + 2;
+            char *c = \"x\";
+            int *z = (int *)8;
+            int a[2] = 1;
+            long addr = (long)&l - 1;
+            int narrow = (int)&l;
+            ",
+            0,
+        )
+        .unwrap();
+        let nodes = parse(&tokens, &spans).0;
+        let mut checker = Checker::new();
+        let number = |n, ty| typed(NodeKind::Number(n), ty);
+        let list = |nodes, ty| typed(NodeKind::InitializerList(nodes), ty);
+        let row = Type::array(Type::Int, 2);
+        // a braced value initializes one element and the rest of the row is 0
+        assert_eq!(
+            checker.node(nodes[0].clone()),
+            Ok(Node::global_definition(
+                "m",
+                Type::array(row.clone(), 2),
+                Some(list(
+                    vec![
+                        list(
+                            vec![number(1, Type::Int), number(2, Type::Int)],
+                            row.clone()
+                        ),
+                        list(vec![number(3, Type::Int)], row.clone()),
+                    ],
+                    Type::array(row, 2)
+                ))
+            ))
+        );
+        let chars = [97, 98, 99].map(|c| number(c, Type::Char)).to_vec();
+        assert_eq!(
+            checker.node(nodes[1].clone()),
+            Ok(Node::global_definition(
+                "s",
+                Type::array(Type::Char, 3),
+                Some(list(chars, Type::array(Type::Char, 3)))
+            ))
+        );
+        assert_eq!(
+            checker.warnings,
+            vec![Warning::ExcessElements(Span::new(0, 64, 3, 25))]
+        );
+        // the offset is in bytes
+        let long = Type::pointer(Type::Long);
+        assert_eq!(
+            checker.node(nodes[3].clone()),
+            Ok(Node::global_definition(
+                "p",
+                long.clone(),
+                Some(typed(
+                    NodeKind::Plus(
+                        Box::new(typed(
+                            NodeKind::Address(Box::new(typed(
+                                NodeKind::GlobalVariable("l".into(), Type::Long),
+                                Type::Long
+                            ))),
+                            long.clone()
+                        )),
+                        Box::new(number(16, Type::Long))
+                    ),
+                    long
+                ))
+            ))
+        );
+        let char_p = Type::pointer(Type::Char);
+        assert_eq!(
+            checker.node(nodes[4].clone()),
+            Ok(Node::global_definition(
+                "c",
+                char_p.clone(),
+                Some(typed(
+                    NodeKind::Address(Box::new(typed(
+                        NodeKind::Str(b"x".to_vec()),
+                        Type::array(Type::Char, 2)
+                    ))),
+                    char_p
+                ))
+            ))
+        );
+        assert_eq!(
+            checker.node(nodes[5].clone()),
+            Ok(Node::global_definition(
+                "z",
+                Type::pointer(Type::Int),
+                Some(number(8, Type::pointer(Type::Int)))
+            ))
+        );
+        assert_eq!(
+            checker.node(nodes[6].clone()),
+            Err(Error::InvalidInitializer(Span::new(0, 203, 8, 24)))
+        );
+        // an address fits in long but not in int
+        assert_eq!(
+            checker.node(nodes[7].clone()),
+            Ok(Node::global_definition(
+                "addr",
+                Type::Long,
+                Some(typed(
+                    NodeKind::Plus(
+                        Box::new(typed(
+                            NodeKind::Address(Box::new(typed(
+                                NodeKind::GlobalVariable("l".into(), Type::Long),
+                                Type::Long
+                            ))),
+                            Type::Long
+                        )),
+                        Box::new(number(-1, Type::Long))
+                    ),
+                    Type::Long
+                ))
+            ))
+        );
+        assert_eq!(
+            checker.node(nodes[8].clone()),
+            Err(Error::NotConstantInitializer(Span::new(0, 269, 10, 26)))
+        );
+    }
+
+    #[test]
+    fn it_call() {
+        let nodes = body(
+            "
+            char f(char c);
+            int main() { long l; f(l); g(f(1)); }
+            ",
+        )
+        .unwrap();
+        assert_eq!(nodes[1].ty(), &Type::Char);
+        assert_eq!(nodes[2].ty(), &Type::Int);
+        match &nodes[2].kind {
+            NodeKind::Call(_, args) => assert_eq!(args[0].ty(), &Type::Int),
+            _ => unreachable!(),
+        }
+
+        // the parameters of printf are unspecified, so the arguments are only promoted
+        let (tokens, spans) = tokenize(
+            "
+            long printf();
+            int f(int a);
+            int f();
+            int main() { char c; printf(\"%d %s\\n\", c, \"x\"); return f(1, 2); }
+            ",
+            0,
+        )
+        .unwrap();
+        let mut checker = Checker::new();
+        let result = checker.program(parse(&tokens, &spans).0);
+        assert_eq!(
+            result,
+            Err(Error::ArgumentCount(
+                "f".into(),
+                1,
+                2,
+                Span::new(0, 142, 5, 68),
+                Span::new(0, 44, 3, 17)
+            ))
+        );
+        let nodes = body(
+            "
+            long printf();
+            int main() { char c; printf(\"%d\\n\", c); }
+            ",
+        )
+        .unwrap();
+        assert_eq!(nodes[1].ty(), &Type::Long);
+        match &nodes[1].kind {
+            NodeKind::Call(_, args) => {
+                assert_eq!(args[0].ty(), &Type::pointer(Type::Char));
+                assert_eq!(args[1].ty(), &Type::Int);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn it_warning() {
+        let (tokens, spans) = tokenize("int main() { g(1); g(2); return f(); }", 0).unwrap();
+        let mut checker = Checker::new();
+        checker.program(parse(&tokens, &spans).0).unwrap();
+        assert_eq!(
+            checker.warnings,
+            vec![
+                Warning::ImplicitDeclaration("g".into(), Span::new(0, 13, 1, 14)),
+                Warning::ImplicitDeclaration("f".into(), Span::new(0, 32, 1, 33)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_error() {
+        assert_eq!(
+            body("int main() { int x; *x; }"),
+            Err(Error::InvalidDereference(
+                Type::Int,
+                Span::new(0, 20, 1, 21)
+            ))
+        );
+        assert_eq!(
+            body("int main() { int *p; p + p; }"),
+            Err(Error::InvalidOperands(
+                Type::pointer(Type::Int),
+                Type::pointer(Type::Int),
+                Span::new(0, 23, 1, 24)
+            ))
+        );
+        assert_eq!(
+            body("int main() { int *p; -p; }"),
+            Err(Error::InvalidOperand(
+                Type::pointer(Type::Int),
+                Span::new(0, 21, 1, 22)
+            ))
+        );
+        assert_eq!(
+            body("int main() { 1 = 2; }"),
+            Err(Error::NotLvalue(Span::new(0, 15, 1, 16)))
+        );
+        assert_eq!(
+            body("int main() { int *p; p = 1; }"),
+            Err(Error::IncompatibleTypes(
+                Type::pointer(Type::Int),
+                Type::Int,
+                Span::new(0, 25, 1, 26)
+            ))
+        );
+        assert_eq!(
+            body("int f(int a); int main() { f(); }"),
+            Err(Error::ArgumentCount(
+                "f".into(),
+                1,
+                0,
+                Span::new(0, 27, 1, 28),
+                Span::new(0, 4, 1, 5)
+            ))
+        );
+    }
+}
@@ -1,8 +1,13 @@
 mod parser;
+mod span;
 mod token;
 
-pub use token::Token;
+pub use parser::Error;
+pub use span::Span;
+pub use token::{Literal, Token};
 
-pub fn tokenize(src: &str) -> Vec<Token> {
-    parser::tokens(src.as_bytes()).expect("TODO").0
+/// the tokens and their positions.
+/// the spans have one more element than the tokens for the end of the input.
+pub fn tokenize(src: &str, file: usize) -> parser::Result<(Vec<Token>, Vec<Span>)> {
+    parser::tokens(src.as_bytes(), file)
 }
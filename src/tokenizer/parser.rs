@@ -1,26 +1,72 @@
-use super::Token;
+use super::{Literal, Span, Token};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
-    Expected(Vec<u8>),
+    Expected(Vec<u8>, Span),
+
+    /// the character does not begin any token
+    Unexpected(u8, Span),
+
+    /// /* without */
+    UnterminatedComment(Span),
+
+    /// the string literal or the character constant is not closed by the quote in the line
+    Unterminated(u8, Span),
+
+    /// ''
+    EmptyCharacter(Span),
+
+    /// the character after the backslash does not begin any escape sequence
+    UnknownEscape(u8, Span),
+
+    /// the octal or hexadecimal escape sequence does not fit in a char
+    EscapeOutOfRange(Span),
+
+    /// the digit is not in the radix of the integer constant
+    InvalidDigit(u8, u32, Span),
+
+    /// the letters after the digits of the integer constant
+    InvalidSuffix(String, Span),
+
+    /// no type can represent the integer constant
+    TooLarge(Span),
 }
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// the scanners below only see the rest of the input,
+    /// so the position is filled when the error reaches `tokens`
+    fn at(self, span: Span) -> Self {
+        match self {
+            Error::Expected(expected, _) => Error::Expected(expected, span),
+            Error::Unexpected(c, _) => Error::Unexpected(c, span),
+            Error::UnterminatedComment(_) => Error::UnterminatedComment(span),
+            Error::Unterminated(quote, _) => Error::Unterminated(quote, span),
+            Error::EmptyCharacter(_) => Error::EmptyCharacter(span),
+            Error::UnknownEscape(c, _) => Error::UnknownEscape(c, span),
+            Error::EscapeOutOfRange(_) => Error::EscapeOutOfRange(span),
+            Error::InvalidDigit(c, radix, _) => Error::InvalidDigit(c, radix, span),
+            Error::InvalidSuffix(suffix, _) => Error::InvalidSuffix(suffix, span),
+            Error::TooLarge(_) => Error::TooLarge(span),
+        }
+    }
+}
+
 impl From<Vec<std::ops::RangeInclusive<u8>>> for Error {
     fn from(rs: Vec<std::ops::RangeInclusive<u8>>) -> Self {
         let mut result = vec![];
         for r in rs {
             for v in r.into_iter() {
-                result.push(u8::from(v));
+                result.push(v);
             }
         }
-        Self::Expected(result)
+        Self::Expected(result, Span::default())
     }
 }
 
 impl From<Vec<u8>> for Error {
     fn from(rs: Vec<u8>) -> Self {
-        Self::Expected(rs)
+        Self::Expected(rs, Span::default())
     }
 }
 
@@ -71,7 +117,7 @@ fn skip_until_end_of_block_comment(src: &[u8]) -> Result<&[u8]> {
     match src {
         [b'*', b'/', tail @ ..] => Ok(tail),
         [_, tail @ ..] => skip_until_end_of_block_comment(tail),
-        _ => Err(Error::from(vec![b'*', b'/'])),
+        _ => Err(Error::UnterminatedComment(Span::default())),
     }
 }
 fn skip_comment_block(src: &[u8]) -> Result<&[u8]> {
@@ -103,32 +149,298 @@ fn ignore_space_and_comment(src: &[u8]) -> Result<&[u8]> {
 /**
  * number
  */
-fn number_inner(src: &[u8]) -> (String, &[u8]) {
-    if let [head, tail @ ..] = src {
-        match head {
-            b'0'..=b'9' => {
-                let (head2, tail) = number_inner(tail);
-                (format!("{}{}", *head as char, head2), tail)
+/// unsigned and long from u, l, ll, ul, lu, ull or llu in any case.
+/// the letters of ll are in the same case
+fn suffix(src: &[u8]) -> Option<(bool, bool)> {
+    let (unsigned, src) = match src {
+        [b'u' | b'U', src @ ..] => (true, src),
+        _ => (false, src),
+    };
+    let (long, src) = match src {
+        [b'l', b'l', src @ ..] | [b'L', b'L', src @ ..] => (true, src),
+        [b'l' | b'L', src @ ..] => (true, src),
+        _ => (false, src),
+    };
+    let (unsigned, src) = match src {
+        [b'u' | b'U', src @ ..] if !unsigned => (true, src),
+        _ => (unsigned, src),
+    };
+    src.is_empty().then_some((unsigned, long))
+}
+
+/// the value is the bits of the constant,
+/// which the type chosen by the parser interprets
+fn number(src: &[u8]) -> Result<(isize, Literal, &[u8])> {
+    let (radix, digits) = match src {
+        [b'0', b'x' | b'X', src @ ..] => (16, src),
+        [b'0', b'b' | b'B', src @ ..] => (2, src),
+        // the leading 0 is an octal digit itself.
+        // 0 alone has the same value and type in any radix and is taken as decimal
+        [b'0', b'0'..=b'9', ..] => (8, src),
+        [b'0'..=b'9', ..] => (10, src),
+        _ => return Err(Error::from(vec![b'0'..=b'9'])),
+    };
+    // the constant lasts as long as the letters and the digits
+    let end = digits
+        .iter()
+        .position(|c| !c.is_ascii_alphanumeric() && *c != b'_')
+        .unwrap_or(digits.len());
+    let (word, rest) = digits.split_at(end);
+    let n_digits = word
+        .iter()
+        .position(|c| !(*c as char).is_digit(radix))
+        .unwrap_or(word.len());
+    let (digits, letters) = word.split_at(n_digits);
+    let invalid_suffix = || {
+        // 0x without digits has the suffix x
+        let suffix = if digits.is_empty() {
+            &src[1..src.len() - rest.len()]
+        } else {
+            letters
+        };
+        Error::InvalidSuffix(String::from_utf8_lossy(suffix).into(), Span::default())
+    };
+    let (unsigned, long) = match letters {
+        [c @ b'0'..=b'9', ..] => return Err(Error::InvalidDigit(*c, radix, Span::default())),
+        // 0x or 0b without digits
+        _ if digits.is_empty() => return Err(invalid_suffix()),
+        letters => suffix(letters).ok_or_else(invalid_suffix)?,
+    };
+
+    let mut value: u64 = 0;
+    for c in digits {
+        let d = (*c as char).to_digit(radix).expect("digit") as u64;
+        value = value
+            .checked_mul(radix as u64)
+            .and_then(|n| n.checked_add(d))
+            .ok_or(Error::TooLarge(Span::default()))?;
+    }
+    let literal = Literal {
+        decimal: radix == 10,
+        unsigned,
+        long,
+    };
+    // only the unsigned types can represent the rest,
+    // which a signed decimal constant can not have
+    if literal.decimal && !unsigned && value > i64::MAX as u64 {
+        return Err(Error::TooLarge(Span::default()));
+    }
+    Ok((value as isize, literal, rest))
+}
+
+#[test]
+fn it_number() {
+    assert_eq!(
+        number("100".as_bytes()),
+        Ok((100, Literal::PLAIN, "".as_bytes()))
+    );
+    let prefixed = Literal {
+        decimal: false,
+        unsigned: false,
+        long: false,
+    };
+    assert_eq!(number(b"0x1fF;"), Ok((0x1ff, prefixed, ";".as_bytes())));
+    assert_eq!(number(b"017"), Ok((0o17, prefixed, "".as_bytes())));
+    assert_eq!(number(b"0b101"), Ok((5, prefixed, "".as_bytes())));
+    assert_eq!(number(b"0"), Ok((0, Literal::PLAIN, "".as_bytes())));
+    assert_eq!(
+        number(b"0xffffffffffffffffULL"),
+        Ok((
+            -1,
+            Literal {
+                decimal: false,
+                unsigned: true,
+                long: true
+            },
+            "".as_bytes()
+        ))
+    );
+    assert_eq!(
+        number(b"10lu+"),
+        Ok((
+            10,
+            Literal {
+                decimal: true,
+                unsigned: true,
+                long: true
+            },
+            "+".as_bytes()
+        ))
+    );
+    assert_eq!(
+        number(b"18446744073709551615u"),
+        Ok((
+            -1,
+            Literal {
+                decimal: true,
+                unsigned: true,
+                long: false
+            },
+            "".as_bytes()
+        ))
+    );
+    assert_eq!(
+        number(b"9223372036854775808"),
+        Err(Error::TooLarge(Span::default()))
+    );
+    assert_eq!(
+        number(b"99999999999999999999"),
+        Err(Error::TooLarge(Span::default()))
+    );
+    assert_eq!(
+        number(b"0x10000000000000000"),
+        Err(Error::TooLarge(Span::default()))
+    );
+    assert_eq!(
+        number(b"09"),
+        Err(Error::InvalidDigit(b'9', 8, Span::default()))
+    );
+    assert_eq!(
+        number(b"0b12"),
+        Err(Error::InvalidDigit(b'2', 2, Span::default()))
+    );
+    assert_eq!(
+        number(b"1lL"),
+        Err(Error::InvalidSuffix("lL".into(), Span::default()))
+    );
+    assert_eq!(
+        number(b"12abc"),
+        Err(Error::InvalidSuffix("abc".into(), Span::default()))
+    );
+    assert_eq!(
+        number(b"0x"),
+        Err(Error::InvalidSuffix("x".into(), Span::default()))
+    );
+}
+
+/**
+ * string literal and character constant
+ */
+/// the value of the digits in the radix, which is at most `max_digits` long
+fn digits(src: &[u8], radix: u32, max_digits: usize) -> (u32, usize) {
+    let mut value: u32 = 0;
+    let mut n = 0;
+    while n < max_digits {
+        match src.get(n).and_then(|c| (*c as char).to_digit(radix)) {
+            // saturated, which is out of range of a char anyway
+            Some(d) => value = value.saturating_mul(radix).saturating_add(d),
+            None => break,
+        }
+        n += 1;
+    }
+    (value, n)
+}
+
+/// the byte which the escape sequence after a backslash stands for
+fn escape(src: &[u8]) -> Result<(u8, &[u8])> {
+    let simple = |c: u8, src| Ok((c, src));
+    match src {
+        [b'n', src @ ..] => simple(b'\n', src),
+        [b't', src @ ..] => simple(b'\t', src),
+        [b'r', src @ ..] => simple(b'\r', src),
+        [b'a', src @ ..] => simple(0x07, src),
+        [b'b', src @ ..] => simple(0x08, src),
+        [b'f', src @ ..] => simple(0x0c, src),
+        [b'v', src @ ..] => simple(0x0b, src),
+        [c @ (b'\\' | b'\'' | b'"' | b'?'), src @ ..] => simple(*c, src),
+        // \0 is the shortest octal escape sequence
+        [b'0'..=b'7', ..] => {
+            let (value, n) = digits(src, 8, 3);
+            let c = u8::try_from(value).map_err(|_| Error::EscapeOutOfRange(Span::default()))?;
+            Ok((c, &src[n..]))
+        }
+        [b'x', tail @ ..] => match digits(tail, 16, usize::MAX) {
+            (_, 0) => Err(Error::from(vec![b'0'..=b'9', b'a'..=b'f', b'A'..=b'F'])),
+            (value, n) => {
+                let c =
+                    u8::try_from(value).map_err(|_| Error::EscapeOutOfRange(Span::default()))?;
+                Ok((c, &tail[n..]))
+            }
+        },
+        [b'\n', ..] | [] => Err(Error::Unterminated(b'"', Span::default())),
+        [c, ..] => Err(Error::UnknownEscape(*c, Span::default())),
+    }
+}
+
+/// the bytes between the quotes, the opening quote is already read
+fn string(src: &[u8]) -> Result<(Vec<u8>, &[u8])> {
+    let mut result = vec![];
+    let mut src = src;
+    loop {
+        match src {
+            [b'"', src @ ..] => return Ok((result, src)),
+            [b'\\', tail @ ..] => {
+                let (c, tail) = escape(tail)?;
+                result.push(c);
+                src = tail;
+            }
+            [b'\n', ..] | [] => return Err(Error::Unterminated(b'"', Span::default())),
+            [c, tail @ ..] => {
+                result.push(*c);
+                src = tail;
             }
-            _ => (String::new(), src),
         }
-    } else {
-        (String::new(), src)
     }
 }
 
-fn number(src: &[u8]) -> Result<(isize, &[u8])> {
-    let (n, src) = number_inner(src);
-    if n == String::new() {
-        Err(Error::from(vec![b'0'..=b'9']))
-    } else {
-        Ok((isize::from_str_radix(n.as_ref(), 10).unwrap(), src))
+/// a character constant is an int with the value of the char,
+/// the opening quote is already read
+fn character(src: &[u8]) -> Result<(isize, &[u8])> {
+    let (c, src) = match src {
+        [b'\'', ..] => return Err(Error::EmptyCharacter(Span::default())),
+        [b'\\', src @ ..] => escape(src).map_err(|e| match e {
+            Error::Unterminated(_, span) => Error::Unterminated(b'\'', span),
+            e => e,
+        })?,
+        [b'\n', ..] | [] => return Err(Error::Unterminated(b'\'', Span::default())),
+        [c, src @ ..] => (*c, src),
+    };
+    match src {
+        // char is signed
+        [b'\'', src @ ..] => Ok((c as i8 as isize, src)),
+        [b'\n', ..] | [] => Err(Error::Unterminated(b'\'', Span::default())),
+        _ => Err(Error::from(vec![b'\''])),
     }
 }
 
 #[test]
-fn it_number() {
-    assert_eq!(number("100".as_bytes()), Ok((100, "".as_bytes())));
+fn it_string() {
+    assert_eq!(
+        string(br#"a\tb\\\"\'\101\0\x41g" x"#),
+        Ok((b"a\tb\\\"'A\0Ag".to_vec(), " x".as_bytes()))
+    );
+    // an octal escape sequence has at most 3 digits
+    assert_eq!(string(br#"\1011""#), Ok((b"A1".to_vec(), "".as_bytes())));
+    assert_eq!(
+        string(b"abc\n\""),
+        Err(Error::Unterminated(b'"', Span::default()))
+    );
+    assert_eq!(
+        string(br#"\q""#),
+        Err(Error::UnknownEscape(b'q', Span::default()))
+    );
+    assert_eq!(
+        string(br#"\x100""#),
+        Err(Error::EscapeOutOfRange(Span::default()))
+    );
+    assert_eq!(
+        string(br#"\400""#),
+        Err(Error::EscapeOutOfRange(Span::default()))
+    );
+}
+
+#[test]
+fn it_character() {
+    assert_eq!(character(b"a'"), Ok((97, "".as_bytes())));
+    assert_eq!(character(br"\n'"), Ok((10, "".as_bytes())));
+    assert_eq!(character(br"\''"), Ok((39, "".as_bytes())));
+    assert_eq!(character(br"\377'"), Ok((-1, "".as_bytes())));
+    assert_eq!(character(b"'"), Err(Error::EmptyCharacter(Span::default())));
+    assert_eq!(
+        character(b"a"),
+        Err(Error::Unterminated(b'\'', Span::default()))
+    );
+    assert_eq!(character(b"ab'"), Err(Error::from(vec![b'\''])));
 }
 
 /**
@@ -137,7 +449,7 @@ fn it_number() {
 fn identity_inner(src: &[u8]) -> (String, &[u8]) {
     if let [head, tail @ ..] = src {
         match head {
-            b'a'..=b'z' | b'_' | b'A'..=b'Z' => {
+            b'a'..=b'z' | b'_' | b'A'..=b'Z' | b'0'..=b'9' => {
                 let (head2, tail) = identity_inner(tail);
                 (format!("{}{}", *head as char, head2), tail)
             }
@@ -150,7 +462,7 @@ fn identity_inner(src: &[u8]) -> (String, &[u8]) {
 fn identity(src: &[u8]) -> Result<(String, &[u8])> {
     if let [head, tail @ ..] = src {
         match head {
-            b'a'..=b'z' | b'A'..=b'Z' => {
+            b'a'..=b'z' | b'_' | b'A'..=b'Z' => {
                 let (head2, tail2) = identity_inner(tail);
                 Ok((format!("{}{}", *head as char, head2), tail2))
             }
@@ -161,6 +473,33 @@ fn identity(src: &[u8]) -> Result<(String, &[u8])> {
     }
 }
 
+/**
+ * keyword
+ * an identity which is reserved by the language
+ */
+fn keyword_or_identity(s: String) -> Token {
+    match s.as_str() {
+        "if" => Token::If,
+        "else" => Token::Else,
+        "for" => Token::For,
+        "while" => Token::While,
+        "do" => Token::Do,
+        "switch" => Token::Switch,
+        "case" => Token::Case,
+        "default" => Token::Default,
+        "goto" => Token::Goto,
+        "break" => Token::Break,
+        "continue" => Token::Continue,
+        "return" => Token::Return,
+        "char" => Token::Char,
+        "short" => Token::Short,
+        "int" => Token::Int,
+        "long" => Token::Long,
+        "void" => Token::Void,
+        _ => Token::Identity(s),
+    }
+}
+
 #[test]
 fn it_identity() {
     assert_eq!(
@@ -178,61 +517,116 @@ fn it_identity() {
  */
 fn token(src: &[u8]) -> Result<(Token, &[u8])> {
     match src {
-        [b'i', b'f', src @ ..] => Ok((Token::If, src)),
-        [b'e', b'l', b's', b'e', src @ ..] => Ok((Token::Else, src)),
-        [b'f', b'o', b'r', src @ ..] => Ok((Token::For, src)),
-        [b'w', b'h', b'i', b'l', b'e', src @ ..] => Ok((Token::While, src)),
-        [b'r', b'e', b't', b'u', b'r', b'n', src @ ..] => Ok((Token::Return, src)),
+        [b'<', b'<', b'=', src @ ..] => Ok((Token::ShiftLeftAssign, src)),
+        [b'>', b'>', b'=', src @ ..] => Ok((Token::ShiftRightAssign, src)),
         [b'&', b'&', src @ ..] => Ok((Token::And, src)),
+        [b'+', b'=', src @ ..] => Ok((Token::PlusAssign, src)),
+        [b'-', b'=', src @ ..] => Ok((Token::MinusAssign, src)),
+        [b'*', b'=', src @ ..] => Ok((Token::MultipleAssign, src)),
+        [b'/', b'=', src @ ..] => Ok((Token::DevideAssign, src)),
+        [b'%', b'=', src @ ..] => Ok((Token::PercentAssign, src)),
+        [b'&', b'=', src @ ..] => Ok((Token::AmpersandAssign, src)),
+        [b'|', b'=', src @ ..] => Ok((Token::PipeAssign, src)),
+        [b'^', b'=', src @ ..] => Ok((Token::CaretAssign, src)),
+        [b'+', b'+', src @ ..] => Ok((Token::Increment, src)),
+        [b'-', b'-', src @ ..] => Ok((Token::Decrement, src)),
         [b'|', b'|', src @ ..] => Ok((Token::Or, src)),
         [b'=', b'=', src @ ..] => Ok((Token::Equal, src)),
         [b'!', b'=', src @ ..] => Ok((Token::NotEqual, src)),
         [b'<', b'=', src @ ..] => Ok((Token::LessEqual, src)),
         [b'>', b'=', src @ ..] => Ok((Token::MoreEqual, src)),
+        [b'<', b'<', src @ ..] => Ok((Token::ShiftLeft, src)),
+        [b'>', b'>', src @ ..] => Ok((Token::ShiftRight, src)),
+        [b'&', src @ ..] => Ok((Token::Ampersand, src)),
+        [b'|', src @ ..] => Ok((Token::Pipe, src)),
+        [b'^', src @ ..] => Ok((Token::Caret, src)),
         [b'+', src @ ..] => Ok((Token::Plus, src)),
         [b'-', src @ ..] => Ok((Token::Minus, src)),
         [b'*', src @ ..] => Ok((Token::Multiple, src)),
         [b'/', src @ ..] => Ok((Token::Devide, src)),
+        [b'%', src @ ..] => Ok((Token::Percent, src)),
         [b'!', src @ ..] => Ok((Token::Not, src)),
+        [b'~', src @ ..] => Ok((Token::Tilde, src)),
         [b'=', src @ ..] => Ok((Token::Assign, src)),
         [b'<', src @ ..] => Ok((Token::Less, src)),
         [b'>', src @ ..] => Ok((Token::More, src)),
         [b';', src @ ..] => Ok((Token::EndExpr, src)),
+        [b'?', src @ ..] => Ok((Token::Question, src)),
+        [b':', src @ ..] => Ok((Token::Colon, src)),
         [b'(', src @ ..] => Ok((Token::LeftParen, src)),
         [b')', src @ ..] => Ok((Token::RightParen, src)),
         [b'{', src @ ..] => Ok((Token::LeftBlock, src)),
         [b'}', src @ ..] => Ok((Token::RightBlock, src)),
-        [b'0'..=b'9', ..] => number(src)
-            .map(|(n, src)| Ok((Token::number(n), src)))
+        [b'[', src @ ..] => Ok((Token::LeftBracket, src)),
+        [b']', src @ ..] => Ok((Token::RightBracket, src)),
+        [b',', src @ ..] => Ok((Token::Comma, src)),
+        [b'"', src @ ..] => string(src).map(|(s, src)| (Token::Str(s), src)),
+        [b'\'', src @ ..] => character(src).map(|(n, src)| (Token::number(n), src)),
+        [b'0'..=b'9', ..] => number(src).map(|(n, literal, src)| (Token::Number(n, literal), src)),
+        [b'a'..=b'z' | b'A'..=b'Z' | b'_', ..] => identity(src)
+            .map(|(s, src)| Ok((keyword_or_identity(s), src)))
             .unwrap(),
-        [b'a'..=b'z' | b'A'..=b'Z', ..] => identity(src)
-            .map(|(s, src)| Ok((Token::identity(s), src)))
-            .unwrap(),
-        _ => Err(Error::from(vec![
-            b'i', b'e', b'f', b'w', b'&', b'|', b'=', b'!', b'<', b'>', b'+', b'-', b'*', b'/',
-            b'!', b'=', b'<', b'>', b';', b'(', b')', b'{', b'}',
-        ])),
+        [c, ..] => Err(Error::Unexpected(*c, Span::default())),
+        [] => Err(Error::Expected(vec![], Span::default())),
     }
 }
 
-pub fn tokens(src: &[u8]) -> Result<(Vec<Token>, &[u8])> {
+pub fn tokens(src: &[u8], file: usize) -> Result<(Vec<Token>, Vec<Span>)> {
     let mut result = vec![];
+    let mut spans = vec![];
+    let mut span = Span::start(file);
     let mut src = src;
     loop {
-        src = ignore_space_and_comment(src)?;
-        if src.len() <= 0 {
+        let rest = ignore_space_and_comment(src).map_err(|e| e.at(span))?;
+        span = span.advance(&src[..src.len() - rest.len()]);
+        src = rest;
+        if src.is_empty() {
             break;
         }
 
         match token(src) {
-            Ok((token, output)) => {
+            Ok((token, rest)) => {
                 result.push(token);
-                src = output;
+                spans.push(span);
+                span = span.advance(&src[..src.len() - rest.len()]);
+                src = rest;
             }
-            Err(e) => return Err(e),
+            Err(e) => return Err(e.at(span)),
         }
     }
-    Ok((result, src))
+    // the end of the input
+    spans.push(span);
+    Ok((result, spans))
+}
+
+#[test]
+fn it_keyword() {
+    assert_eq!(
+        tokens(
+            "int format(char a, long b2, short shorter) { return a; }".as_bytes(),
+            0
+        )
+        .map(|(tokens, _)| tokens),
+        Ok(vec![
+            Token::Int,
+            Token::identity("format"),
+            Token::LeftParen,
+            Token::Char,
+            Token::identity("a"),
+            Token::Comma,
+            Token::Long,
+            Token::identity("b2"),
+            Token::Comma,
+            Token::Short,
+            Token::identity("shorter"),
+            Token::RightParen,
+            Token::LeftBlock,
+            Token::Return,
+            Token::identity("a"),
+            Token::EndExpr,
+            Token::RightBlock,
+        ])
+    );
 }
 
 #[test]
@@ -245,30 +639,71 @@ fn it_tokens() {
             // b = 5 * 6 - 8;
             a + b / 2;
             "
-            .as_bytes()
-        ),
-        Ok((
-            vec![
-                Token::identity("a"),
-                Token::Assign,
-                Token::Number(3),
-                Token::EndExpr,
-                Token::identity("b"),
-                Token::Assign,
-                Token::Number(5),
-                Token::Multiple,
-                Token::Number(6),
-                Token::Minus,
-                Token::Number(8),
-                Token::EndExpr,
-                Token::identity("a"),
-                Token::Plus,
-                Token::identity("b"),
-                Token::Devide,
-                Token::Number(2),
-                Token::EndExpr,
-            ],
-            "".as_bytes()
-        ))
+            .as_bytes(),
+            0
+        )
+        .map(|(tokens, _)| tokens),
+        Ok(vec![
+            Token::identity("a"),
+            Token::Assign,
+            Token::number(3),
+            Token::EndExpr,
+            Token::identity("b"),
+            Token::Assign,
+            Token::number(5),
+            Token::Multiple,
+            Token::number(6),
+            Token::Minus,
+            Token::number(8),
+            Token::EndExpr,
+            Token::identity("a"),
+            Token::Plus,
+            Token::identity("b"),
+            Token::Devide,
+            Token::number(2),
+            Token::EndExpr,
+        ])
+    );
+}
+
+#[test]
+fn it_operator() {
+    // the longest operator is taken
+    assert_eq!(
+        tokens("a<<=b>>c+++d-=-1[0]".as_bytes(), 0).map(|(tokens, _)| tokens),
+        Ok(vec![
+            Token::identity("a"),
+            Token::ShiftLeftAssign,
+            Token::identity("b"),
+            Token::ShiftRight,
+            Token::identity("c"),
+            Token::Increment,
+            Token::Plus,
+            Token::identity("d"),
+            Token::MinusAssign,
+            Token::Minus,
+            Token::number(1),
+            Token::LeftBracket,
+            Token::number(0),
+            Token::RightBracket,
+        ])
+    );
+}
+
+#[test]
+fn it_span() {
+    assert_eq!(
+        tokens("a =\n  // b\n  12;\n".as_bytes(), 1).map(|(_, spans)| spans),
+        Ok(vec![
+            Span::new(1, 0, 1, 1),
+            Span::new(1, 2, 1, 3),
+            Span::new(1, 13, 3, 3),
+            Span::new(1, 15, 3, 5),
+            Span::new(1, 17, 4, 1),
+        ])
+    );
+    assert_eq!(
+        tokens("a = 1;\n  $".as_bytes(), 0),
+        Err(Error::Unexpected(b'$', Span::new(0, 9, 2, 3)))
     );
 }
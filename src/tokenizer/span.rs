@@ -0,0 +1,39 @@
+/// a position in the source files
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Span {
+    /// index of the input file
+    pub file: usize,
+
+    /// bytes from the beginning of the file
+    pub offset: usize,
+
+    /// 1-origin
+    pub line: usize,
+
+    /// 1-origin, counted in bytes
+    pub col: usize,
+}
+
+impl Span {
+    pub fn new(file: usize, offset: usize, line: usize, col: usize) -> Self {
+        Span {
+            file,
+            offset,
+            line,
+            col,
+        }
+    }
+
+    /// the beginning of the file
+    pub fn start(file: usize) -> Self {
+        Span::new(file, 0, 1, 1)
+    }
+
+    /// the position just after the bytes which start at this position
+    pub fn advance(self, bytes: &[u8]) -> Self {
+        bytes.iter().fold(self, |span, b| match b {
+            b'\n' => Span::new(span.file, span.offset + 1, span.line + 1, 1),
+            _ => Span::new(span.file, span.offset + 1, span.line, span.col + 1),
+        })
+    }
+}
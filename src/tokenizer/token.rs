@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Token {
     /// +
@@ -12,9 +14,30 @@ pub enum Token {
     /// /
     Devide,
 
+    /// %
+    Percent,
+
     /// !
     Not,
 
+    /// ~
+    Tilde,
+
+    /// &
+    Ampersand,
+
+    /// |
+    Pipe,
+
+    /// ^
+    Caret,
+
+    /// <<
+    ShiftLeft,
+
+    /// >>
+    ShiftRight,
+
     /// &&
     And,
 
@@ -24,6 +47,42 @@ pub enum Token {
     /// =
     Assign,
 
+    /// +=
+    PlusAssign,
+
+    /// -=
+    MinusAssign,
+
+    /// *=
+    MultipleAssign,
+
+    /// /=
+    DevideAssign,
+
+    /// %=
+    PercentAssign,
+
+    /// &=
+    AmpersandAssign,
+
+    /// |=
+    PipeAssign,
+
+    /// ^=
+    CaretAssign,
+
+    /// <<=
+    ShiftLeftAssign,
+
+    /// >>=
+    ShiftRightAssign,
+
+    /// ++
+    Increment,
+
+    /// --
+    Decrement,
+
     /// ==
     Equal,
 
@@ -45,6 +104,12 @@ pub enum Token {
     /// ;
     EndExpr,
 
+    /// ?
+    Question,
+
+    /// :
+    Colon,
+
     /// (
     LeftParen,
 
@@ -57,6 +122,15 @@ pub enum Token {
     /// }
     RightBlock,
 
+    /// [
+    LeftBracket,
+
+    /// ]
+    RightBracket,
+
+    /// ,
+    Comma,
+
     /// return
     Return,
 
@@ -72,11 +146,72 @@ pub enum Token {
     /// for
     For,
 
-    Number(isize),
+    /// do
+    Do,
+
+    /// switch
+    Switch,
+
+    /// case
+    Case,
+
+    /// default
+    Default,
+
+    /// goto
+    Goto,
+
+    /// break
+    Break,
+
+    /// continue
+    Continue,
+
+    /// char
+    Char,
+
+    /// short
+    Short,
+
+    /// int
+    Int,
+
+    /// long
+    Long,
+
+    /// void
+    Void,
+
+    /// the value and how the integer constant is written
+    Number(isize, Literal),
+
+    /// the bytes of the string literal without the terminating null character
+    Str(Vec<u8>),
 
     Identity(String),
 }
 
+/// the form of an integer constant, which decides its type together with its value
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Literal {
+    /// without the prefix 0x, 0b or 0
+    pub decimal: bool,
+
+    /// with the suffix u or U
+    pub unsigned: bool,
+
+    /// with the suffix l, L, ll or LL
+    pub long: bool,
+}
+
+impl Literal {
+    pub const PLAIN: Literal = Literal {
+        decimal: true,
+        unsigned: false,
+        long: false,
+    };
+}
+
 impl Token {
     pub fn identity<S>(s: S) -> Self
     where
@@ -85,7 +220,90 @@ impl Token {
         Token::Identity(s.into())
     }
 
+    /// a decimal constant without any suffix
     pub fn number(n: isize) -> Self {
-        Token::Number(n)
+        Token::Number(n, Literal::PLAIN)
+    }
+}
+
+/// the token as it is written in the source
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Multiple => write!(f, "*"),
+            Token::Devide => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Not => write!(f, "!"),
+            Token::Tilde => write!(f, "~"),
+            Token::Ampersand => write!(f, "&"),
+            Token::Pipe => write!(f, "|"),
+            Token::Caret => write!(f, "^"),
+            Token::ShiftLeft => write!(f, "<<"),
+            Token::ShiftRight => write!(f, ">>"),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Assign => write!(f, "="),
+            Token::PlusAssign => write!(f, "+="),
+            Token::MinusAssign => write!(f, "-="),
+            Token::MultipleAssign => write!(f, "*="),
+            Token::DevideAssign => write!(f, "/="),
+            Token::PercentAssign => write!(f, "%="),
+            Token::AmpersandAssign => write!(f, "&="),
+            Token::PipeAssign => write!(f, "|="),
+            Token::CaretAssign => write!(f, "^="),
+            Token::ShiftLeftAssign => write!(f, "<<="),
+            Token::ShiftRightAssign => write!(f, ">>="),
+            Token::Increment => write!(f, "++"),
+            Token::Decrement => write!(f, "--"),
+            Token::Equal => write!(f, "=="),
+            Token::NotEqual => write!(f, "!="),
+            Token::Less => write!(f, "<"),
+            Token::LessEqual => write!(f, "<="),
+            Token::More => write!(f, ">"),
+            Token::MoreEqual => write!(f, ">="),
+            Token::EndExpr => write!(f, ";"),
+            Token::Question => write!(f, "?"),
+            Token::Colon => write!(f, ":"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::LeftBlock => write!(f, "{{"),
+            Token::RightBlock => write!(f, "}}"),
+            Token::LeftBracket => write!(f, "["),
+            Token::RightBracket => write!(f, "]"),
+            Token::Comma => write!(f, ","),
+            Token::Return => write!(f, "return"),
+            Token::If => write!(f, "if"),
+            Token::Else => write!(f, "else"),
+            Token::While => write!(f, "while"),
+            Token::For => write!(f, "for"),
+            Token::Do => write!(f, "do"),
+            Token::Switch => write!(f, "switch"),
+            Token::Case => write!(f, "case"),
+            Token::Default => write!(f, "default"),
+            Token::Goto => write!(f, "goto"),
+            Token::Break => write!(f, "break"),
+            Token::Continue => write!(f, "continue"),
+            Token::Char => write!(f, "char"),
+            Token::Short => write!(f, "short"),
+            Token::Int => write!(f, "int"),
+            Token::Long => write!(f, "long"),
+            Token::Void => write!(f, "void"),
+            Token::Number(n, _) => write!(f, "{}", n),
+            Token::Str(s) => {
+                write!(f, "\"")?;
+                for c in s {
+                    match c {
+                        b'"' | b'\\' => write!(f, "\\{}", *c as char)?,
+                        b' '..=b'~' => write!(f, "{}", *c as char)?,
+                        // 3 digits so that a following digit is not taken in
+                        c => write!(f, "\\{:03o}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Token::Identity(s) => write!(f, "{}", s),
+        }
     }
 }
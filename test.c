@@ -1,9 +1,12 @@
-n = 0;
-m = 0;
+int main() {
+  int n = 0;
+  int m = 0;
+  int i;
 
-for (i = 0; i < 10; i = i+1)  {
-  n = n + i;
-  m = m + i;
-}
+  for (i = 0; i < 10; i++)  {
+    n += i;
+    m += i;
+  }
 
-n + m; // 110
+  return n + m; // 90
+}
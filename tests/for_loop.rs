@@ -0,0 +1,118 @@
+//! for loops compiled by this compiler exit with the same status as the ones compiled by gcc
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// the shapes of the loops, each program returns a value which depends on the order of the clauses
+const CASES: [(&str, &str); 16] = [
+    (
+        "sum",
+        "int main() { int s; int i; s = 0; for (i = 0; i < 10; i = i + 1) s = s + i; return s; }",
+    ),
+    (
+        "step_after_body",
+        "int main() { int s; int i; s = 0; for (i = 0; i < 3; i = i + 1) { if (i == 0) s = s + 10; s = s + i; } return s; }",
+    ),
+    (
+        "no_init",
+        "int main() { int i; i = 4; for (; i < 9; i++) ; return i; }",
+    ),
+    (
+        "no_condition",
+        "int main() { int i; for (i = 0; ; i++) if (i == 7) break; return i; }",
+    ),
+    (
+        "no_step",
+        "int main() { int i; int s; s = 0; for (i = 0; i < 5;) { s += i; i++; } return s; }",
+    ),
+    (
+        "forever",
+        "int main() { int i; i = 0; for (;;) { i += 3; if (i > 20) break; } return i; }",
+    ),
+    (
+        "continue_runs_step",
+        "int main() { int i; int s; s = 0; for (i = 0; i < 10; i++) { if (i % 3) continue; s += i; } return s * 10 + i; }",
+    ),
+    (
+        "nested",
+        "int main() { int i; int j; int s; s = 0; for (i = 0; i < 4; i++) for (j = i; j < 4; j++) s = s * 2 + j - i; return s % 256; }",
+    ),
+    (
+        "comma",
+        "int main() { int i; int j; int s; s = 0; for (i = 0, j = 10; i < j; i++, j--) s += j - i; return s; }",
+    ),
+    (
+        "not_entered",
+        "int main() { int i; int s; s = 5; for (i = 10; i < 3; i++) s = 0; return s + i; }",
+    ),
+    (
+        "empty_body",
+        "int main() { int i; for (i = 0; i < 42; i++); return i; }",
+    ),
+    (
+        "down",
+        "int main() { int i; int s; s = 0; for (i = 10; i > 0; i--) s = s * 3 + i; return s % 256; }",
+    ),
+    (
+        "goto_out",
+        "int main() { int i; for (i = 0; i < 100; i++) if (i == 13) goto out; return 0; out: return i; }",
+    ),
+    (
+        "evaluation_count",
+        "int f(int *n) { *n += 1; return *n < 5; }
+         int g(int *n) { *n += 10; return 0; }
+         int main() { int c; int s; c = 0; s = 0; for (g(&s); f(&c); g(&s)) ; return c * 100 + s; }",
+    ),
+    (
+        "declaration_in_body",
+        "int main() { int i; int s; s = 0; for (i = 0; i < 4; i++) { int x; x = i * i; s += x; } return s; }",
+    ),
+    (
+        "loop_in_loop_break",
+        "int main() { int i; int j; int s; s = 0; for (i = 0; i < 5; i++) { for (j = 0; ; j++) if (j >= i) break; s += j; } return s; }",
+    ),
+];
+
+fn temporary(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("c-for-loop-{}-{}", std::process::id(), name))
+}
+
+/// compiles the source with the command and runs the executable.
+/// the exit status, or None when the compilation fails
+fn run(compiler: &str, name: &str, src: &str) -> Option<i32> {
+    let source = temporary(&format!("{}.c", name));
+    let executable = temporary(&format!("{}-{}", name, compiler.replace('/', "_")));
+    fs::write(&source, src).unwrap();
+    let compiled = Command::new(compiler)
+        .arg("-o")
+        .arg(&executable)
+        .arg(&source)
+        .status()
+        .unwrap()
+        .success();
+    let status = if compiled {
+        Command::new(&executable).status().unwrap().code()
+    } else {
+        None
+    };
+    let _ = fs::remove_file(&source);
+    let _ = fs::remove_file(&executable);
+    status
+}
+
+#[test]
+fn it_for_loop() {
+    let compiler = env!("CARGO_BIN_EXE_c");
+    let mut failures = vec![];
+    for (name, src) in CASES {
+        let expected = run("gcc", name, src);
+        let actual = run(compiler, name, src);
+        assert!(expected.is_some(), "gcc failed on {}", name);
+        if actual != expected {
+            failures.push(format!("{}: {:?}, gcc {:?}", name, actual, expected));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}